@@ -0,0 +1,128 @@
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+fn main() {
+    assert_eq!(describe(Shape::Row(vec![1, 2, 3])), "row summing to 6");
+    assert_eq!(
+        describe(Shape::Row(vec![1, -2, 3])),
+        "row with a negative at 1"
+    );
+    assert_eq!(
+        describe(Shape::Grid(vec![vec![1], vec![2, 3]])),
+        "grid of 3 cells"
+    );
+    assert_eq!(
+        describe(Shape::Grid(vec![vec![1], vec![]])),
+        "grid with an empty row at 1"
+    );
+    assert_eq!(describe(Shape::Empty), "empty");
+
+    assert_eq!(clamp_first(&[4, 12, 7], 10), 10);
+    assert_eq!(clamp_first(&[4, 7], 10), 0);
+    assert_eq!(clamp_first(&[], 10), -1);
+
+    assert_eq!(checked_index(&[3, 5, 8], 5), 1);
+    assert_eq!(checked_index(&[3, 5, 8], 8), 2);
+    assert_eq!(first_even_or_bail(&[1, 4]), Ok(4));
+    assert_eq!(first_even_or_bail(&[1, 0]), Err("zero"));
+}
+
+enum Shape {
+    Row(Vec<i32>),
+    Grid(Vec<Vec<i32>>),
+    Empty,
+}
+
+// Rust can't label a `match` arm directly but wrapping the arm's body in a labeled block gives the
+// loop a value-producing position to break out to.
+fn describe(shape: Shape) -> String {
+    match shape {
+        Shape::Row(values) => 'arm: {
+            let mut sum = 0;
+            cbit!(for (i, &value) in each(&values) break 'arm {
+                if value < 0 {
+                    break 'arm format!("row with a negative at {i}");
+                }
+                sum += value;
+            });
+            format!("row summing to {sum}")
+        }
+        Shape::Grid(rows) => 'arm: {
+            let mut cells = 0;
+            cbit!('rows: for (i, row) in each(&rows) break 'arm {
+                if row.is_empty() {
+                    break 'arm format!("grid with an empty row at {i}");
+                }
+                cbit!(for _ in each(row) break loop 'rows {
+                    cells += 1;
+                });
+            });
+            format!("grid of {cells} cells")
+        }
+        Shape::Empty => "empty".to_string(),
+    }
+}
+
+// The same goes for the branches of an `if`.
+fn clamp_first(values: &[i32], max: i32) -> i32 {
+    if values.is_empty() {
+        -1
+    } else {
+        'branch: {
+            cbit!(for (_, &value) in each(values) break 'branch {
+                if value > max {
+                    break 'branch max;
+                }
+            });
+            0
+        }
+    }
+}
+
+// The type of the values broken out to a label is inferred from the labeled block like it is for
+// a regular `break`, even when every other path out of the block, or some of the `break` values
+// themselves, diverge.
+fn checked_index(values: &[i32], needle: i32) -> usize {
+    'search: {
+        match values.first() {
+            Some(_) => {
+                cbit!(for (i, &value) in each(values) break 'search {
+                    if value == needle {
+                        break 'search i;
+                    }
+                    if value > needle {
+                        panic!("{needle} is missing from the sorted values");
+                    }
+                });
+                unreachable!("{needle} is past the end of the sorted values")
+            }
+            None => panic!("no values"),
+        }
+    }
+}
+
+fn first_even_or_bail(values: &[i32]) -> Result<i32, &'static str> {
+    let found = 'found: {
+        cbit!(for (_, &value) in each(values) break 'found {
+            if value == 0 {
+                return Err("zero");
+            }
+            if value % 2 == 0 {
+                break 'found value;
+            }
+        });
+        unreachable!("no even value")
+    };
+    Ok(found)
+}
+
+fn each<'a, T, B>(
+    items: &'a [T],
+    mut f: impl FnMut((usize, &'a T)) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    for item in items.iter().enumerate() {
+        f(item)?;
+    }
+    ControlFlow::Continue(())
+}
@@ -3,6 +3,10 @@ use std::{hint::black_box, ops::ControlFlow};
 fn main() {
     dbg!(regular(black_box(10)));
     dbg!(cbit(black_box(10)));
+    dbg!(regular_continue(black_box(10)));
+    dbg!(cbit_continue(black_box(10)));
+    dbg!(for_range(black_box(10)));
+    dbg!(cbit_dyn(black_box(10)));
 }
 
 // See the assembly with `cargo asm --example asm asm::regular`.
@@ -75,3 +79,73 @@ fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B>
     }
     ControlFlow::Continue(())
 }
+
+// Plain `continue`s hand `Default::default()` back to the iterator. The macro cannot tell whether the
+// `Continue` type is `()` but, for the usual unit case, the call is inlined away entirely.
+//
+// Compare `cargo asm --example asm asm::regular_continue` with
+// `cargo asm --example asm asm::cbit_continue`. On my machine, both compile down to the same
+// branchless loop unrolled twice and neither contains a `call`. They only differ in register
+// allocation.
+#[inline(never)]
+pub fn regular_continue(n: u64) -> u64 {
+    let mut c = 0;
+    for i in 0..n {
+        if i % 3 == 0 {
+            continue;
+        }
+        c += i;
+    }
+    c
+}
+
+#[inline(never)]
+pub fn cbit_continue(n: u64) -> u64 {
+    let mut c = 0;
+    cbit::cbit!(for i in up_to(n) {
+        if i % 3 == 0 {
+            continue;
+        }
+        c += i;
+    });
+    c
+}
+
+// `for_range!` generates its own iterator function. Compare `cargo asm --example asm asm::for_range`
+// with `asm::regular` above. On my machine, they are so identical that LLVM merges them and `main`
+// ends up calling `asm::regular` in both cases.
+#[inline(never)]
+pub fn for_range(n: u64) -> u64 {
+    let mut c = 0;
+    cbit::for_range!(0..n, |i| c += i);
+    c
+}
+
+// The expansion ends the body's `async` block with an `unreachable!()` after a never-ready future
+// and `expect`s the resolution stored before it once the body is pending. Even when the iterator is
+// called through a vtable and cannot be inlined, both panics are optimized out along with the
+// `expect` message: at `opt-level` 1, 2, 3, `s`, and `z`, the only panic left in this example on my
+// machine is the one for iterators which break without their `#[dyn_callback]` callback breaking.
+// `unreachable_unchecked` and `unwrap_unchecked` would therefore gain nothing here.
+#[inline(never)]
+pub fn cbit_dyn(n: u64) -> u64 {
+    let mut c = 0;
+    cbit::cbit!(
+        #[dyn_callback]
+        for i in up_to_dyn(n) {
+            if i == 7 {
+                break;
+            }
+            c += i;
+        }
+    );
+    c
+}
+
+#[inline(never)]
+fn up_to_dyn(n: u64, f: &mut dyn FnMut(u64) -> ControlFlow<()>) -> ControlFlow<()> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
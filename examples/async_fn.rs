@@ -0,0 +1,129 @@
+use std::{
+    future::Future,
+    ops::ControlFlow,
+    pin::{pin, Pin},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+use cbit::cbit;
+
+// `cbit!` loops can be used within `async` code. The body itself is synchronous and is polled with
+// its own no-op waker, which never reaches the executor driving the surrounding future. Real
+// `.await`s around the loop suspend and wake the surrounding future as usual.
+fn main() {
+    let (result, wakes) = block_on(process(&[3, 1, 4, 1, 5, 9, 2, 6]));
+    assert_eq!(result, (14, 31, Some(4)));
+
+    // Each `yield_now` wakes the executor exactly once; none of the loops' polls woke it.
+    assert_eq!(wakes, 3);
+
+    // The same goes for futures driven on another thread.
+    let handle = thread::spawn(|| block_on(process(&[2, 7, 1, 8])));
+    assert_eq!(handle.join().unwrap(), ((18, 18, Some(1)), 3));
+}
+
+async fn process(values: &[u32]) -> (u32, u32, Option<usize>) {
+    yield_now().await;
+
+    let mut sum = 0;
+    cbit!(for (i, &value) in each(values) {
+        if i == 5 {
+            break;
+        }
+        sum += value;
+    });
+
+    yield_now().await;
+
+    let position = 'found: {
+        cbit!(for (i, &value) in each(values) break 'found {
+            if value > 4 {
+                break 'found Some(i);
+            }
+        });
+        None
+    };
+
+    // Loops can also be nested within `async` blocks of their own.
+    let total = async {
+        yield_now().await;
+        let mut total = 0;
+        cbit!(for (_, &value) in each(values) {
+            total += value;
+        });
+        total
+    }
+    .await;
+
+    (sum, total, position)
+}
+
+fn each<'a, T, B>(
+    items: &'a [T],
+    mut f: impl FnMut((usize, &'a T)) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    for item in items.iter().enumerate() {
+        f(item)?;
+    }
+    ControlFlow::Continue(())
+}
+
+/// A future which suspends once, waking its task right away.
+fn yield_now() -> impl Future<Output = ()> {
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    YieldNow(false)
+}
+
+/// Drives a future to completion on the current thread, returning its output along with the
+/// number of times it was woken.
+fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+    struct ThreadWaker {
+        thread: Thread,
+        wakes: AtomicUsize,
+    }
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_by_ref();
+        }
+
+        fn wake_by_ref(self: &Arc<Self>) {
+            self.wakes.fetch_add(1, Ordering::SeqCst);
+            self.thread.unpark();
+        }
+    }
+
+    let state = Arc::new(ThreadWaker {
+        thread: thread::current(),
+        wakes: AtomicUsize::new(0),
+    });
+    let waker = Waker::from(state.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return (output, state.wakes.load(Ordering::SeqCst));
+        }
+        thread::park();
+    }
+}
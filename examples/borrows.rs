@@ -0,0 +1,92 @@
+use std::{collections::BTreeMap, ops::ControlFlow};
+
+use cbit::cbit;
+
+fn main() {
+    let mut map = BTreeMap::new();
+    map.insert("a".to_string(), vec![1u8, 2]);
+    map.insert("b".to_string(), vec![3u8]);
+
+    assert_eq!(total_len(&map), 3);
+    assert_eq!(first_key_with(&map, 3), Some("b"));
+
+    push_len(&mut map);
+    assert_eq!(map["a"], [1, 2, 1]);
+    assert_eq!(map["b"], [3, 1]);
+
+    let mut values = vec![1, 2, 3];
+    increment_all(&mut values);
+    assert_eq!(values, [2, 3, 4]);
+
+    assert_eq!(first_above_mut(&mut values, 2).map(std::mem::take), Some(3));
+    assert_eq!(values, [2, 0, 4]);
+}
+
+fn total_len(map: &BTreeMap<String, Vec<u8>>) -> usize {
+    let mut total = 0;
+    cbit!(for (key, value) in map_each(map) {
+        assert!(!key.is_empty());
+        total += value.len();
+    });
+    total
+}
+
+fn first_key_with(map: &BTreeMap<String, Vec<u8>>, needle: u8) -> Option<&str> {
+    cbit!(for (key, value) in map_each(map) {
+        if value.contains(&needle) {
+            return Some(key.as_str());
+        }
+    });
+    None
+}
+
+fn push_len(map: &mut BTreeMap<String, Vec<u8>>) {
+    cbit!(for (key, value) in map_each_mut(map) {
+        value.push(key.len() as u8);
+    });
+}
+
+fn increment_all(values: &mut [i32]) {
+    cbit!(for x in each_mut(values) {
+        *x += 1;
+    });
+}
+
+fn first_above_mut(values: &mut [i32], min: i32) -> Option<&mut i32> {
+    cbit!(for x in each_mut(values) {
+        if *x > min {
+            return Some(x);
+        }
+    });
+    None
+}
+
+fn each_mut<'a, T, B>(
+    values: &'a mut [T],
+    mut f: impl FnMut(&'a mut T) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    for value in values {
+        f(value)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn map_each<'a, K, V, B>(
+    map: &'a BTreeMap<K, V>,
+    mut f: impl FnMut((&'a K, &'a V)) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    for entry in map {
+        f(entry)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn map_each_mut<'a, K, V, B>(
+    map: &'a mut BTreeMap<K, V>,
+    mut f: impl FnMut((&'a K, &'a mut V)) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    for entry in map {
+        f(entry)?;
+    }
+    ControlFlow::Continue(())
+}
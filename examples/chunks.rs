@@ -0,0 +1,56 @@
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+fn main() {
+    let values = (0..10).collect::<Vec<u32>>();
+
+    // Const generic arguments are passed through the iterator call's turbofish as-is.
+    let mut lens = Vec::new();
+    cbit!(for chunk in chunks::<4, _, _>(&values) {
+        lens.push(chunk.len());
+    });
+    assert_eq!(lens, [4, 4, 2]);
+
+    let mut lens = Vec::new();
+    cbit!(
+        #[hoist_args]
+        for chunk in chunks::<{ 2 + 3 }, _, _>(&values) {
+            lens.push(chunk.len());
+        }
+    );
+    assert_eq!(lens, [5, 5]);
+
+    let mut count = 0;
+    cbit!(for _ in chunks::<4, u32, _>(&[]) {
+        count += 1;
+    });
+    assert_eq!(count, 0);
+
+    assert_eq!(first_with_sum_above::<3>(&values, 10), Some(&[3, 4, 5][..]));
+    assert_eq!(first_with_sum_above::<3>(&values, 100), None);
+
+    // The built-in equivalent takes the size as a regular argument.
+    let mut lens = Vec::new();
+    cbit::chunks!(&values, 3, |chunk| lens.push(chunk.len()));
+    assert_eq!(lens, [3, 3, 3, 1]);
+}
+
+fn first_with_sum_above<const N: usize>(values: &[u32], min: u32) -> Option<&[u32]> {
+    cbit!(for chunk in chunks::<N, _, _>(values) {
+        if chunk.iter().sum::<u32>() > min {
+            return Some(chunk);
+        }
+    });
+    None
+}
+
+fn chunks<'a, const N: usize, T, B>(
+    values: &'a [T],
+    mut f: impl FnMut(&'a [T]) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    for chunk in values.chunks(N) {
+        f(chunk)?;
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,90 @@
+// The expansion is clean under clippy's default, pedantic, and nursery lints. The workspace lints
+// its examples with `-D warnings` so any lint the expansion triggers fails the build.
+//
+// Like rustc, clippy skips most lints in code expanded from another crate's macros but lints on the
+// user's own loops still see the expansion. For instance, `clippy::needless_continue` used to flag
+// the `continue 'rows` which ends the expansion in `outer`.
+//
+// Without `legacy-waker`, the expansion polls with `Waker::noop`, which is newer than the crate's
+// MSRV, so only the `legacy-waker` expansion is held to it.
+#![deny(clippy::all, clippy::pedantic, clippy::nursery)]
+#![cfg_attr(not(feature = "legacy-waker"), allow(clippy::incompatible_msrv))]
+
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+fn main() {
+    assert_eq!(basic(5), 10);
+    assert_eq!(labeled(10), 6);
+    assert_eq!(outer(&[vec![1, 2], vec![3, 0, 5], vec![6]]), (12, 2, 3));
+    assert_eq!(find(10), Some(3));
+}
+
+fn basic(n: u64) -> u64 {
+    let mut sum = 0;
+    cbit!(for i in up_to(n) {
+        sum += i;
+    });
+    sum
+}
+
+fn labeled(n: u64) -> u64 {
+    let mut sum = 0;
+    cbit!('virt: for i in up_to(n) {
+        if i == 4 {
+            break 'virt;
+        }
+        sum += i;
+    });
+    sum
+}
+
+fn outer(rows: &[Vec<u64>]) -> (u64, u64, u64) {
+    let mut sum = 0;
+    'rows: for row in rows {
+        cbit!(for &item in each(row) break for 'rows {
+            if item == 0 {
+                continue 'rows;
+            }
+            sum += item;
+        });
+    }
+
+    let mut attempts = 0;
+    let found = 'retry: loop {
+        attempts += 1;
+        cbit!(for i in up_to(attempts) break loop 'retry {
+            if i == 2 {
+                break 'retry i;
+            }
+        });
+    };
+
+    (sum, found, attempts)
+}
+
+fn find(n: u64) -> Option<u64> {
+    'found: {
+        cbit!(#[hoist_args] for i in up_to(n) break out 'found {
+            if i * i > n / 2 {
+                break out Some(i);
+            }
+        });
+        None
+    }
+}
+
+fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn each<'a, T, B>(items: &'a [T], mut f: impl FnMut(&'a T) -> ControlFlow<B>) -> ControlFlow<B> {
+    for item in items {
+        f(item)?;
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,81 @@
+use std::{cell::RefCell, ops::ControlFlow};
+
+use cbit::cbit;
+
+fn main() {
+    let log = RefCell::new(Vec::new());
+    cbit!(for token in drain(&log, 3) {
+        log.borrow_mut().push(format!("body {}", token.0));
+    });
+    assert_eq!(
+        *log.borrow(),
+        [
+            "yield 0", "body 0", "drop 0", //
+            "yield 1", "body 1", "drop 1", //
+            "yield 2", "body 2", "drop 2",
+        ]
+    );
+
+    let log = RefCell::new(Vec::new());
+    let kept = early_exit(&log);
+    assert_eq!(kept.0, 1);
+    assert_eq!(
+        *log.borrow(),
+        [
+            "yield 0", "drop 0", //
+            "yield 1", "drop 2", // The rest of the queue is dropped by the iterator itself.
+        ]
+    );
+    drop(kept);
+    assert_eq!(log.borrow().last().unwrap(), "drop 1");
+
+    let log = RefCell::new(Vec::new());
+    let kept = moved_out(&log);
+    assert_eq!(kept.iter().map(|v| v.0).collect::<Vec<_>>(), [0, 2]);
+    assert_eq!(*log.borrow(), ["yield 0", "yield 1", "drop 1", "yield 2"]);
+    drop(kept);
+    assert_eq!(log.borrow()[4..], ["drop 0", "drop 2"]);
+}
+
+// Each owned element is dropped exactly once, when the body run that received it ends.
+struct Token<'a>(u32, &'a RefCell<Vec<String>>);
+
+impl Drop for Token<'_> {
+    fn drop(&mut self) {
+        self.1.borrow_mut().push(format!("drop {}", self.0));
+    }
+}
+
+// An element that the body returns is moved out of the loop rather than dropped with the rest of the
+// body's state.
+fn early_exit(log: &RefCell<Vec<String>>) -> Token<'_> {
+    cbit!(for token in drain(log, 3) {
+        if token.0 == 1 {
+            return token;
+        }
+    });
+    unreachable!()
+}
+
+fn moved_out(log: &RefCell<Vec<String>>) -> Vec<Token<'_>> {
+    let mut kept = Vec::new();
+    cbit!(for token in drain(log, 3) {
+        if token.0 % 2 == 0 {
+            kept.push(token);
+        }
+    });
+    kept
+}
+
+fn drain<'a, B>(
+    log: &'a RefCell<Vec<String>>,
+    n: u32,
+    mut f: impl FnMut(Token<'a>) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    let mut queue = (0..n).map(|i| Token(i, log)).collect::<Vec<_>>();
+    for token in queue.drain(..) {
+        log.borrow_mut().push(format!("yield {}", token.0));
+        f(token)?;
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,57 @@
+// Crates which forbid `unsafe` can use the macro. Without the default `legacy-waker` feature, the
+// expansion contains no `unsafe` at all; that feature constructs its waker in an `unsafe` block.
+//
+// Note that rustc doesn't lint code expanded from another crate's macros so this example builds with
+// `legacy-waker` as well. It checks that the attribute doesn't get in the way, not that the
+// expansion is free of `unsafe`.
+#![forbid(unsafe_code)]
+
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+fn main() {
+    let mut sum = 0;
+    'outer: for round in 0..3 {
+        cbit!(#[hoist_args] for i in up_to(round + 5) break for 'outer {
+            if i == 3 {
+                continue 'outer;
+            }
+            sum += i;
+        });
+    }
+    assert_eq!(sum, 9);
+
+    let mut found = None;
+    cbit!(
+        #[dyn_callback]
+        for i in up_to_dyn(10) {
+            if i * i > 20 {
+                found = Some(i);
+                break;
+            }
+        }
+    );
+    assert_eq!(found, Some(5));
+
+    assert_eq!(
+        cbit::find_map!(up_to(10), |i| (i > 6).then_some(i * 2)),
+        Some(14)
+    );
+    assert_eq!(cbit::sum!(up_to(4)), 6);
+    assert_eq!(cbit::collect!(up_to(3)), [0, 1, 2]);
+}
+
+fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn up_to_dyn(n: u64, f: &mut dyn FnMut(u64) -> ControlFlow<()>) -> ControlFlow<()> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,94 @@
+use std::{fmt::Debug, ops::ControlFlow};
+
+use cbit::cbit;
+
+fn main() {
+    assert_eq!(cloned(&["a".to_string(), "b".to_string()]), ["a", "b"]);
+    assert_eq!(described(&[Some(1), None]), "Some(1), None");
+    assert_eq!(largest(&[3, 9, 4]), Some(&9));
+    assert_eq!(largest::<u8>(&[]), None);
+    assert_eq!(total_area(&[Square(2.0), Square(3.0)]), 13.0);
+    assert_eq!(
+        first_long(&["ab", "abcd", "abcdef"], 3),
+        Some("abcd".to_string())
+    );
+}
+
+// The element type comes from the iterator function's signature so bounds on it are available in
+// the body, even when the enclosing function is generic over it as well.
+fn cloned<T: Clone>(items: &[T]) -> Vec<T> {
+    let mut out = Vec::new();
+    cbit!(for item in each(items) {
+        out.push(item.clone());
+    });
+    out
+}
+
+fn described<T: Debug>(items: &[T]) -> String {
+    let mut out = String::new();
+    cbit!(for item in each(items) {
+        if !out.is_empty() {
+            out.push_str(", ");
+        }
+        out.push_str(&format!("{item:?}"));
+    });
+    out
+}
+
+fn largest<T: Ord>(items: &[T]) -> Option<&T> {
+    let mut largest = None;
+    cbit!(for item in each(items) {
+        if largest.map_or(true, |largest| item > largest) {
+            largest = Some(item);
+        }
+    });
+    largest
+}
+
+// Iterator functions can require bounds of their own, which the body can then rely on.
+trait Shape {
+    fn area(&self) -> f64;
+}
+
+struct Square(f64);
+
+impl Shape for Square {
+    fn area(&self) -> f64 {
+        self.0 * self.0
+    }
+}
+
+fn each_shape<'a, S: Shape, B>(
+    shapes: &'a [S],
+    mut f: impl FnMut(&'a S) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    for shape in shapes {
+        f(shape)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn total_area<S: Shape>(shapes: &[S]) -> f64 {
+    let mut total = 0.0;
+    cbit!(for shape in each_shape(shapes) {
+        total += shape.area();
+    });
+    total
+}
+
+// Trait methods on the element can also produce the loop's value.
+fn first_long<T: AsRef<str> + ToString>(items: &[T], min: usize) -> Option<String> {
+    cbit!(for item in each(items) {
+        if item.as_ref().len() > min {
+            return Some(item.to_string());
+        }
+    });
+    None
+}
+
+fn each<'a, T, B>(items: &'a [T], mut f: impl FnMut(&'a T) -> ControlFlow<B>) -> ControlFlow<B> {
+    for item in items {
+        f(item)?;
+    }
+    ControlFlow::Continue(())
+}
@@ -5,6 +5,20 @@ use cbit::cbit;
 fn main() {
     assert_eq!(demo(&[1, 2, 3]), 6);
     assert_eq!(demo(&[1, 2, 3, 4, 101, 8]), -1);
+
+    // The accumulator is moved out of the loop and the function rather than copied.
+    let (joined, buffer) = join(&["a", "b", "c"]);
+    assert_eq!(joined, "abc");
+    assert_eq!(joined.as_ptr(), buffer);
+
+    assert_eq!(running_max(&[3, 1, 4, 1, 5]), 5);
+}
+
+// A body which hands a value back on its first statement never completes normally.
+fn running_max(list: &[i32]) -> i32 {
+    cbit!(for (accum, value) in reduce(i32::MIN, list) {
+        continue with accum.max(*value);
+    })
 }
 
 fn demo(list: &[i32]) -> i32 {
@@ -16,6 +30,23 @@ fn demo(list: &[i32]) -> i32 {
     })
 }
 
+fn join(words: &[&str]) -> (String, *const u8) {
+    let mut buffer = std::ptr::null();
+    let joined = concat(words, &mut buffer);
+    (joined, buffer)
+}
+
+// `cbit!` in tail position evaluates straight to the iterator's `Continue` value.
+fn concat(words: &[&str], buffer: &mut *const u8) -> String {
+    cbit!(
+        for (mut accum, word) in reduce(String::with_capacity(16), words) {
+            accum.push_str(word);
+            *buffer = accum.as_ptr();
+            accum
+        }
+    )
+}
+
 fn reduce<T, I: IntoIterator, B>(
     initial: T,
     values: I,
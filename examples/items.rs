@@ -0,0 +1,93 @@
+use std::{fmt::Write, ops::ControlFlow};
+
+use cbit::cbit;
+
+// Loop bodies can define items just like the body of a regular loop. They are scoped to the body
+// and their own control flow (e.g. a `return` or `?` in an inner `fn`) stays within them.
+fn main() {
+    let mut sums = Vec::new();
+    cbit!(for i in up_to(4) {
+        #[derive(Debug, PartialEq)]
+        struct Pair(u64, u64);
+
+        impl Pair {
+            fn sum(&self) -> u64 {
+                self.0 + self.1
+            }
+        }
+
+        const OFFSET: u64 = 10;
+
+        fn double(x: u64) -> u64 {
+            x * 2
+        }
+
+        if i == 1 {
+            continue;
+        }
+        sums.push(Pair(double(i), OFFSET).sum());
+    });
+    assert_eq!(sums, [10, 14, 16]);
+
+    let mut text = String::new();
+    cbit!(for i in up_to(3) {
+        enum Kind {
+            Even,
+            Odd,
+        }
+
+        trait Describe {
+            fn describe(&self) -> &'static str;
+        }
+
+        impl Describe for Kind {
+            fn describe(&self) -> &'static str {
+                match self {
+                    Kind::Even => "even",
+                    Kind::Odd => "odd",
+                }
+            }
+        }
+
+        macro_rules! kind_of {
+            ($v:expr) => {
+                if $v % 2 == 0 {
+                    Kind::Even
+                } else {
+                    Kind::Odd
+                }
+            };
+        }
+
+        write!(text, "{}{i} ", kind_of!(i).describe()).unwrap();
+    });
+    assert_eq!(text, "even0 odd1 even2 ");
+
+    assert_eq!(first_failure(&["1", "2", "x", "4"]), Some(2));
+    assert_eq!(first_failure(&["1"]), None);
+}
+
+fn first_failure(items: &[&str]) -> Option<u64> {
+    cbit!(for i in up_to(items.len() as u64) {
+        // The `?` and `return` here belong to `parse` rather than to the loop.
+        fn parse(item: &str) -> Result<u64, String> {
+            let value = item.parse::<u64>().map_err(|err| err.to_string())?;
+            if value > 100 {
+                return Err("too large".to_string());
+            }
+            Ok(value)
+        }
+
+        if parse(items[i as usize]).is_err() {
+            return Some(i);
+        }
+    });
+    None
+}
+
+fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
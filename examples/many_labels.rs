@@ -0,0 +1,77 @@
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+fn main() {
+    for target in 0..14 {
+        assert_eq!(escape(target), target.min(12));
+    }
+}
+
+// Loops may declare any number of external labels.
+fn escape(target: u32) -> u32 {
+    'l0: {
+        'l1: {
+            'l2: {
+                'l3: {
+                    'l4: {
+                        'l5: {
+                            'l6: {
+                                'l7: {
+                                    'l8: {
+                                        'l9: {
+                                            'l10: {
+                                                'l11: {
+                                                    cbit!(for i in up_to(target + 1) break 'l0, 'l1, 'l2, 'l3, 'l4, 'l5, 'l6, 'l7, 'l8, 'l9, 'l10, 'l11 {
+                                                        if i != target {
+                                                            continue;
+                                                        }
+                                                        match i {
+                                                            0 => break 'l0,
+                                                            1 => break 'l1,
+                                                            2 => break 'l2,
+                                                            3 => break 'l3,
+                                                            4 => break 'l4,
+                                                            5 => break 'l5,
+                                                            6 => break 'l6,
+                                                            7 => break 'l7,
+                                                            8 => break 'l8,
+                                                            9 => break 'l9,
+                                                            10 => break 'l10,
+                                                            11 => break 'l11,
+                                                            _ => {}
+                                                        }
+                                                    });
+                                                    return 12;
+                                                }
+                                                return 11;
+                                            }
+                                            return 10;
+                                        }
+                                        return 9;
+                                    }
+                                    return 8;
+                                }
+                                return 7;
+                            }
+                            return 6;
+                        }
+                        return 5;
+                    }
+                    return 4;
+                }
+                return 3;
+            }
+            return 2;
+        }
+        return 1;
+    }
+    0
+}
+
+fn up_to<B>(n: u32, mut f: impl FnMut(u32) -> ControlFlow<B>) -> ControlFlow<B> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
@@ -1,4 +1,4 @@
-use std::ops::ControlFlow;
+use std::{cell::RefCell, ops::ControlFlow};
 
 use cbit::cbit;
 
@@ -6,6 +6,134 @@ fn main() {
     cbit!(for _ in Demo.method::<_>() {
         println!("We ran!");
     });
+
+    let grid = Grid {
+        cells: vec![1u32, 2, 3, 4],
+    };
+    assert_eq!(grid.total(), 10);
+    assert_eq!(grid.first_above(2), Some(&3));
+
+    let walker = Walker {
+        driver: up_to,
+        len: 4,
+    };
+    let mut sum = 0;
+    cbit!(for i in (walker.driver)(walker.len) {
+        sum += i;
+    });
+    assert_eq!(sum, 6);
+
+    assert_eq!(visit_order(false), [0, 1, 2]);
+    assert_eq!(visit_order(true), [2, 1, 0]);
+
+    let mut tally = Tally {
+        name: "evens".to_string(),
+        counts: Vec::new(),
+    };
+    tally.record(5);
+    assert_eq!(tally.counts, [0, 2, 4]);
+    assert_eq!(tally.count_below(3), 2);
+    assert_eq!(tally.into_name_if_above(3), Some("evens".to_string()));
+
+    let mut shelf = Shelf {
+        items: vec!["apple".to_string(), "banana".to_string()],
+        fallback: "nothing".to_string(),
+    };
+    assert_eq!(shelf.find("ba"), "banana");
+    assert_eq!(shelf.find(""), "nothing");
+    assert_eq!(shelf.find("cherry"), "nothing");
+    shelf.find_mut("ap").push('!');
+    shelf.find_mut("cherry").push('?');
+    assert_eq!(shelf.items, ["apple!", "banana"]);
+    assert_eq!(shelf.fallback, "nothing?");
+
+    assert_eq!(
+        chained_receiver(false),
+        ["pipeline", "stages", "skip", "limit", "walk", "1", "2"]
+    );
+    assert_eq!(
+        chained_receiver(true),
+        ["limit", "pipeline", "stages", "skip", "walk", "1", "2"]
+    );
+}
+
+// The function to call can also be computed by a block.
+fn visit_order(reversed: bool) -> Vec<u32> {
+    let mut order = Vec::new();
+    cbit!(for i in {
+        order.reserve(3);
+        if reversed {
+            down_to
+        } else {
+            up_to
+        }
+    }(3)
+    {
+        order.push(i);
+    });
+    order
+}
+
+// The receiver can be a whole method chain, each step of which runs exactly once and before the
+// arguments of the final call, like it would in any other call. With `#[hoist_args]`, the arguments
+// run first instead since only they are hoisted.
+fn chained_receiver(hoisted: bool) -> Vec<String> {
+    let log = RefCell::new(Vec::new());
+    let log_step = |step: &str| log.borrow_mut().push(step.to_string());
+    let pipeline = |label| {
+        log_step(label);
+        Pipeline {
+            log: &log,
+            stages: vec![0, 1, 2, 3],
+        }
+    };
+    let limit = |max| {
+        log_step("limit");
+        max
+    };
+
+    if hoisted {
+        cbit!(
+            #[hoist_args]
+            for stage in pipeline("pipeline").stages().skip(1).walk(limit(2)) {
+                log_step(&stage.to_string());
+            }
+        );
+    } else {
+        cbit!(
+            for stage in pipeline("pipeline").stages().skip(1).walk(limit(2)) {
+                log_step(&stage.to_string());
+            }
+        );
+    }
+
+    log.into_inner()
+}
+
+struct Pipeline<'a> {
+    log: &'a RefCell<Vec<String>>,
+    stages: Vec<u32>,
+}
+
+impl Pipeline<'_> {
+    fn stages(self) -> Self {
+        self.log.borrow_mut().push("stages".to_string());
+        self
+    }
+
+    fn skip(mut self, n: usize) -> Self {
+        self.log.borrow_mut().push("skip".to_string());
+        self.stages.drain(..n);
+        self
+    }
+
+    fn walk<B>(&self, max: usize, mut f: impl FnMut(u32) -> ControlFlow<B>) -> ControlFlow<B> {
+        self.log.borrow_mut().push("walk".to_string());
+        for &stage in self.stages.iter().take(max) {
+            f(stage)?;
+        }
+        ControlFlow::Continue(())
+    }
 }
 
 struct Demo;
@@ -15,3 +143,141 @@ impl Demo {
         f(())
     }
 }
+
+// Associated functions can be called through `Self`, even on generic types.
+struct Grid<T> {
+    cells: Vec<T>,
+}
+
+impl<T: Copy + Into<u64> + PartialOrd> Grid<T> {
+    fn each<'a, B>(&'a self, mut f: impl FnMut(&'a T) -> ControlFlow<B>) -> ControlFlow<B> {
+        for cell in &self.cells {
+            f(cell)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn total(&self) -> u64 {
+        let mut total = 0;
+        cbit!(for cell in Self::each(self) {
+            total += (*cell).into();
+        });
+        total
+    }
+
+    fn first_above(&self, min: T) -> Option<&T> {
+        cbit!(for cell in Self::each::<_>(self) {
+            if *cell > min {
+                return Some(cell);
+            }
+        });
+        None
+    }
+}
+
+// Bodies capture `self` like any other variable of the enclosing method.
+struct Tally {
+    name: String,
+    counts: Vec<u32>,
+}
+
+impl Tally {
+    fn record(&mut self, n: u32) {
+        cbit!(for i in up_to(n) {
+            if self.is_interesting(i) {
+                self.counts.push(i);
+            }
+        });
+    }
+
+    fn is_interesting(&self, i: u32) -> bool {
+        i % 2 == 0
+    }
+
+    fn count_below(&self, max: u32) -> usize {
+        let mut count = 0;
+        cbit!(for i in up_to(max) {
+            count += self.counts.iter().filter(|&&v| v == i).count();
+        });
+        count
+    }
+
+    // Since the body is a closure which may run many times, it cannot move out of `self`, not even
+    // right before returning. Moves have to happen after the loop instead.
+    fn into_name_if_above(self, min: u32) -> Option<String> {
+        let mut found = false;
+        cbit!(for &count in each(&self.counts) {
+            if count > min {
+                found = true;
+                break;
+            }
+        });
+        found.then_some(self.name)
+    }
+}
+
+// Shared borrows of `self` can be returned from the body like from any loop.
+struct Shelf {
+    items: Vec<String>,
+    fallback: String,
+}
+
+impl Shelf {
+    fn find(&self, prefix: &str) -> &str {
+        cbit!(for i in up_to(self.items.len() as u32) {
+            let item = &self.items[i as usize];
+            if prefix.is_empty() {
+                return &self.fallback;
+            }
+            if item.starts_with(prefix) {
+                return item;
+            }
+        });
+        &self.fallback
+    }
+
+    // A mutable borrow captured by the body can't escape it since the body is an `FnMut` closure,
+    // which may run again and reborrow it. Breaking out with an index and borrowing after the loop
+    // works instead.
+    fn find_mut(&mut self, prefix: &str) -> &mut String {
+        let found = 'found: {
+            cbit!(for i in up_to(self.items.len() as u32) break 'found {
+                if self.items[i as usize].starts_with(prefix) {
+                    break 'found Some(i as usize);
+                }
+            });
+            None
+        };
+        match found {
+            Some(i) => &mut self.items[i],
+            None => &mut self.fallback,
+        }
+    }
+}
+
+fn each<'a, T, B>(items: &'a [T], mut f: impl FnMut(&'a T) -> ControlFlow<B>) -> ControlFlow<B> {
+    for item in items {
+        f(item)?;
+    }
+    ControlFlow::Continue(())
+}
+
+// Iterator functions can also be injected through fields and called through them.
+struct Walker<D> {
+    driver: D,
+    len: u32,
+}
+
+fn up_to<B>(n: u32, mut f: impl FnMut(u32) -> ControlFlow<B>) -> ControlFlow<B> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn down_to<B>(n: u32, mut f: impl FnMut(u32) -> ControlFlow<B>) -> ControlFlow<B> {
+    for i in (0..n).rev() {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,198 @@
+use std::{num::ParseIntError, ops::ControlFlow};
+
+use cbit::cbit;
+
+fn main() {
+    assert_eq!(closures(&["1", "x", "3"]), (4, 1));
+    assert_eq!(nested_loops(4), 3);
+    assert_eq!(parse_sum(&["1", "2"]), Ok(3));
+    assert!(parse_sum(&["1", "x"]).is_err());
+    assert_eq!(wrapped(4), 6);
+    assert_eq!(unlabeled_continues(&[1, -2, 30, 4, 0, 5]), (13, 1));
+    assert_eq!(forwarded(&[3, 1, 4]), (8, 8, 3));
+    assert_eq!(forwarded_label(&[vec![1, 2], vec![3, 0, 5], vec![6]]), 12);
+}
+
+// Control flow inside closures and `async` blocks belongs to them, not to the `cbit!` loop.
+fn closures(items: &[&str]) -> (u32, u32) {
+    let mut sum = 0;
+    let mut failures = 0;
+
+    cbit!(for item in each(items) {
+        let parse = |v: &str| -> Result<u32, ParseIntError> {
+            let mut total = 0;
+            'digits: for c in v.chars() {
+                if c == '_' {
+                    continue 'digits;
+                }
+                total = total * 10 + c.to_string().parse::<u32>()?;
+            }
+            Ok(total)
+        };
+
+        let pending = async {
+            std::future::ready(()).await;
+            'block: {
+                break 'block 1;
+            }
+        };
+        drop(pending);
+
+        match parse(item) {
+            Ok(v) => sum += v,
+            Err(_) => failures += 1,
+        }
+    });
+
+    (sum, failures)
+}
+
+// Labels used by an inner `cbit!` loop are its own business.
+fn nested_loops(n: u32) -> u32 {
+    let mut count = 0;
+
+    'outer: {
+        cbit!('rows: for row in up_to(n) break 'outer {
+            cbit!('cols: for col in up_to(n) break loop 'rows, 'outer {
+                if col > row {
+                    continue 'rows;
+                }
+                if row == 3 {
+                    break 'outer;
+                }
+                if col == 1 {
+                    break 'cols;
+                }
+                count += 1;
+            });
+        });
+    }
+
+    count
+}
+
+// Loops generated by another macro, including ones nested within each other, don't interfere with
+// one another or with the labels of the code they are expanded into.
+macro_rules! count_pairs {
+    ($label:lifetime, $n:expr, |$a:ident, $b:ident| $cond:expr) => {{
+        let mut count = 0;
+        cbit!($label: for $a in up_to($n) {
+            cbit!(for $b in up_to($n) break loop $label {
+                if $b >= $a {
+                    continue $label;
+                }
+                if $cond {
+                    count += 1;
+                }
+            });
+        });
+        count
+    }};
+}
+
+fn wrapped(n: u32) -> u32 {
+    let mut total = 0;
+    'outer: for i in 0..2 {
+        if i == 1 {
+            continue 'outer;
+        }
+        total += count_pairs!('pairs, n, |a, b| a != b);
+    }
+    total
+}
+
+// Calls passed in as `$iter:expr` reach `cbit!` wrapped in an invisible group.
+macro_rules! tally {
+    ($iter:expr) => {{
+        let mut tally = 0;
+        cbit!(for &item in $iter {
+            tally += item;
+        });
+        tally
+    }};
+}
+
+fn forwarded(items: &[u32]) -> (u32, u32, u32) {
+    let by_method = tally!(items.iter().try_for_each());
+    macro_rules! count {
+        ($iter:expr) => {
+            cbit::sum!($iter)
+        };
+    }
+    (tally!(each(items)), by_method, count!(up_to(3)))
+}
+
+// Labels handed to a macro invocation may be continued by its expansion, which we can't see.
+fn forwarded_label(rows: &[Vec<u32>]) -> u32 {
+    macro_rules! skip_row {
+        ($label:lifetime) => {
+            continue $label
+        };
+    }
+
+    let mut sum = 0;
+    'rows: for row in rows {
+        cbit!(for &item in each(row) break for 'rows {
+            if item == 0 {
+                skip_row!('rows);
+            }
+            sum += item;
+        });
+    }
+    sum
+}
+
+// Unlabeled `continue`s target the innermost loop, just like in Rust. That's the virtual loop
+// unless the body has a loop of its own around the `continue`.
+fn unlabeled_continues(items: &[i32]) -> (i32, u32) {
+    let mut sum = 0;
+    let mut zeros_skipped = 0;
+
+    cbit!(for &item in each(items) {
+        match item.signum() {
+            -1 => continue,
+            0 => {
+                zeros_skipped += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let mut digits = item;
+        loop {
+            if digits >= 10 {
+                digits /= 10;
+                // Continues the inner `loop`, not the virtual loop.
+                continue;
+            }
+            break;
+        }
+
+        sum += digits;
+    });
+
+    (sum, zeros_skipped)
+}
+
+// `?` in the body propagates out of the enclosing function like in a regular loop.
+fn parse_sum(items: &[&str]) -> Result<u32, ParseIntError> {
+    let mut sum = 0;
+    cbit!(for item in each(items) {
+        sum += item.parse::<u32>()?;
+    });
+    Ok(sum)
+}
+
+fn each<'a, T, B>(items: &'a [T], mut f: impl FnMut(&'a T) -> ControlFlow<B>) -> ControlFlow<B> {
+    for item in items {
+        f(item)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn up_to<B>(n: u32, mut f: impl FnMut(u32) -> ControlFlow<B>) -> ControlFlow<B> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,130 @@
+// `cbit!` loops never allocate and only refer to `core`, so they work in `no_std` crates without a
+// global allocator. This example is a `no_std` crate whose `::std` paths don't resolve since it
+// only links `std` under another name, which it uses to install an allocator that counts every
+// allocation made while the loops run.
+//
+// The helper macros building collections (`collect!` and `par_collect!`) allocate, of course, and
+// `timed!` needs `std`. The `#[trace]` option logs to stderr so it needs `std` as well.
+#![no_std]
+
+extern crate std as host;
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ops::ControlFlow,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use cbit::cbit;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { host::alloc::System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { host::alloc::System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn main() {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let results = run_loops();
+    let after = ALLOCATIONS.load(Ordering::Relaxed);
+
+    assert_eq!(results, (10, Some(4), 12, 4, Err(3), Some(5)));
+    assert_eq!(after - before, 0);
+}
+
+fn run_loops() -> (u64, Option<u64>, u64, u64, Result<(), u64>, Option<u64>) {
+    let mut sum = 0;
+    cbit!(for i in up_to(5) {
+        sum += i;
+    });
+
+    let found = 'found: {
+        cbit!(for i in up_to(10) break out 'found {
+            if i * i > 10 {
+                break out Some(i);
+            }
+        });
+        None
+    };
+
+    let mut outer_sum = 0;
+    'outer: for round in 0..3 {
+        cbit!('inner: for i in up_to(round + 5) break for 'outer {
+            if i == 3 {
+                continue 'outer;
+            }
+            if i == 1 {
+                continue 'inner;
+            }
+            outer_sum += i + round;
+        });
+    }
+
+    let total = cbit!(for (total, i) in accumulate(4) {
+        if i % 2 == 0 {
+            continue with total;
+        }
+        total + i
+    });
+
+    let failed = fail_at(3);
+
+    let mut dyn_found = None;
+    cbit!(
+        #[dyn_callback]
+        for i in up_to_dyn(10) {
+            if i == 5 {
+                dyn_found = Some(i);
+                break;
+            }
+        }
+    );
+
+    (sum, found, outer_sum, total, failed, dyn_found)
+}
+
+fn fail_at(n: u64) -> Result<(), u64> {
+    cbit!(try for i in up_to(10) {
+        if i == n {
+            break Err(i);
+        }
+    });
+    Ok(())
+}
+
+fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn up_to_dyn(n: u64, f: &mut dyn FnMut(u64) -> ControlFlow<()>) -> ControlFlow<()> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn accumulate<B>(
+    n: u64,
+    mut f: impl FnMut((u64, u64)) -> ControlFlow<B, u64>,
+) -> ControlFlow<B, u64> {
+    let mut total = 0;
+    for i in 0..n {
+        total = f((total, i))?;
+    }
+    ControlFlow::Continue(total)
+}
@@ -0,0 +1,68 @@
+use std::{fmt::Debug, ops::ControlFlow};
+
+use cbit::cbit;
+
+fn main() {
+    assert_eq!(evens_from(3).collect::<Vec<_>>(), [4, 6, 8]);
+    assert_eq!(evens_from(20).count(), 0);
+
+    assert_eq!(describe(10).to_string(), "found 3");
+    assert_eq!(describe(2).to_string(), "none below 2");
+
+    assert_eq!(only_from_body(), vec![4]);
+
+    let readings = Readings(vec![1, 7, 2, 9]);
+    assert_eq!(readings.starting_above(5).collect::<Vec<_>>(), [&7, &2, &9]);
+    assert_eq!(readings.starting_above(10).count(), 0);
+}
+
+// Values returned from the body define the function's opaque return type just like the values
+// returned from anywhere else in the function.
+fn evens_from(start: u64) -> impl Iterator<Item = u64> {
+    cbit!(for i in up_to(10) {
+        if i >= start {
+            return (i + i % 2..10).step_by(2);
+        }
+    });
+    (0..0).step_by(2)
+}
+
+fn describe(n: u64) -> impl std::fmt::Display {
+    cbit!(for i in up_to(n) {
+        if i == 3 {
+            return format!("found {i}");
+        }
+    });
+    format!("none below {n}")
+}
+
+// The body can even be the only place defining the type.
+fn only_from_body() -> impl Debug + PartialEq<Vec<u64>> {
+    cbit!(for i in up_to(10) {
+        if i == 4 {
+            return vec![i];
+        }
+    });
+    unreachable!()
+}
+
+// Opaque types capturing borrows of `self` work the same way.
+struct Readings(Vec<u32>);
+
+impl Readings {
+    fn starting_above(&self, min: u32) -> impl Iterator<Item = &u32> {
+        cbit!(for i in up_to(self.0.len() as u64) {
+            if self.0[i as usize] > min {
+                return self.0[i as usize..].iter();
+            }
+        });
+        self.0[..0].iter()
+    }
+}
+
+fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,57 @@
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+fn main() {
+    assert_eq!(demo(&[1, 2, 3]), Ok(6));
+    assert_eq!(demo(&[1, 2, 3, 4, 101, 8]), Err(101));
+
+    assert_eq!(shadowed(&[1, 2, 3]), Ok(12));
+    assert_eq!(shadowed(&[5, 20, 1]), Err(20));
+}
+
+fn demo(list: &[i32]) -> Result<i32, i32> {
+    'sum: {
+        let sum = cbit!(for (accum, value) in reduce(0, list) break out 'sum {
+            if *value > 100 {
+                break out Err(*value);
+            }
+            accum + value
+        });
+        Ok(sum)
+    }
+}
+
+// `break out` keeps its regular meaning within native loops, where it can break with the value of a
+// variable named `out`, and closures can still take parameters named `out`.
+fn shadowed(list: &[i32]) -> Result<i32, i32> {
+    'sum: {
+        let sum = cbit!(for (accum, value) in reduce(0, list) break out 'sum {
+            let mut out = *value;
+            let doubled = loop {
+                if out >= value * 2 {
+                    break out;
+                }
+                out += value;
+            };
+            let too_large = |out: i32| out > 30;
+            if too_large(doubled) {
+                break out Err(*value);
+            }
+            accum + doubled
+        });
+        Ok(sum)
+    }
+}
+
+fn reduce<T, I: IntoIterator, B>(
+    initial: T,
+    values: I,
+    mut f: impl FnMut((T, I::Item)) -> ControlFlow<B, T>,
+) -> ControlFlow<B, T> {
+    let mut accum = initial;
+    for value in values {
+        accum = f((accum, value))?;
+    }
+    ControlFlow::Continue(accum)
+}
@@ -0,0 +1,72 @@
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+fn main() {
+    let mut buf = [0u32; 4];
+    let mut next = 10;
+    cbit!(for out in fill_each(&mut buf) {
+        *out = next;
+        next += 1;
+    });
+    assert_eq!(buf, [10, 11, 12, 13]);
+
+    // The iterator checks each slot right after the body filled it.
+    let mut written = Vec::new();
+    cbit!(for (i, slot) in fill_with_scratch(4) {
+        if i == 2 {
+            break;
+        }
+        slot.push_str("hi");
+        written.push(i);
+    });
+    assert_eq!(written, [0, 1]);
+
+    assert_eq!(collect_filled(3), ["0!", "1!", "2!"]);
+}
+
+// C-style iterators hand out a place for the body to fill rather than a value.
+fn fill_each<'a, B>(
+    buf: &'a mut [u32],
+    mut f: impl FnMut(&'a mut u32) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    for slot in buf {
+        f(slot)?;
+    }
+    ControlFlow::Continue(())
+}
+
+// Here, the out-parameter only lives for the duration of each call to the body.
+fn fill_with_scratch<B>(
+    n: usize,
+    mut f: impl FnMut((usize, &mut String)) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    let mut scratch = String::new();
+    for i in 0..n {
+        scratch.clear();
+        f((i, &mut scratch))?;
+        assert_eq!(scratch, "hi");
+    }
+    ControlFlow::Continue(())
+}
+
+fn collect_filled(n: usize) -> Vec<String> {
+    let mut out = Vec::new();
+    cbit!(for slot in fill_and_collect(n, &mut out) {
+        slot.push('!');
+    });
+    out
+}
+
+fn fill_and_collect<B>(
+    n: usize,
+    out: &mut Vec<String>,
+    mut f: impl FnMut(&mut String) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    for i in 0..n {
+        let mut value = i.to_string();
+        f(&mut value)?;
+        out.push(value);
+    }
+    ControlFlow::Continue(())
+}
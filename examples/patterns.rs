@@ -0,0 +1,98 @@
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+// Elements are moved into the body's binding, so struct patterns can move non-`Copy` fields out of
+// them just like they would in a regular `for` loop.
+fn main() {
+    let mut names = Vec::new();
+    cbit!(for Point { x, name } in points() {
+        names.push(format!("{name}{x}"));
+    });
+    assert_eq!(names, ["a1", "b2"]);
+
+    let mut names = Vec::new();
+    cbit!(for Point { name, .. } in points() {
+        names.push(name);
+    });
+    assert_eq!(names, ["a", "b"]);
+
+    let mut bytes = Vec::new();
+    cbit!(for Wrapper(label, data) in wrappers() {
+        bytes.push((label, data.len()));
+    });
+    assert_eq!(bytes, [("one".to_string(), 1), ("two".to_string(), 2)]);
+
+    // Nested patterns can mix moves, `ref` bindings, and wildcards.
+    let mut seen = Vec::new();
+    cbit!(for Outer {
+        inner: Wrapper(label, _),
+        point: Point { ref name, x },
+    } in outers()
+    {
+        seen.push(format!("{label}:{name}:{x}"));
+    });
+    assert_eq!(seen, ["one:a:1", "two:b:2"]);
+
+    // Struct patterns combine with `@`-bindings, `mut` bindings, `first(...)`, and hoisted arguments.
+    let mut labels = Vec::new();
+    cbit!(for wrapper @ Wrapper(..) first(is_first) in wrappers() {
+        labels.push((wrapper.0, is_first));
+    });
+    assert_eq!(
+        labels,
+        [("one".to_string(), true), ("two".to_string(), false)]
+    );
+
+    let mut names = Vec::new();
+    cbit!(
+        #[hoist_args]
+        for Point { mut x, name } in points() {
+            x *= 10;
+            names.push(format!("{name}{x}"));
+        }
+    );
+    assert_eq!(names, ["a10", "b20"]);
+}
+
+struct Point {
+    x: i32,
+    name: String,
+}
+
+struct Wrapper(String, Vec<u8>);
+
+struct Outer {
+    inner: Wrapper,
+    point: Point,
+}
+
+fn points<B>(mut f: impl FnMut(Point) -> ControlFlow<B>) -> ControlFlow<B> {
+    for (x, name) in [(1, "a"), (2, "b")] {
+        f(Point {
+            x,
+            name: name.to_string(),
+        })?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn wrappers<B>(mut f: impl FnMut(Wrapper) -> ControlFlow<B>) -> ControlFlow<B> {
+    for (label, len) in [("one", 1), ("two", 2)] {
+        f(Wrapper(label.to_string(), vec![0; len]))?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn outers<B>(mut f: impl FnMut(Outer) -> ControlFlow<B>) -> ControlFlow<B> {
+    for (x, name, label) in [(1, "a", "one"), (2, "b", "two")] {
+        f(Outer {
+            inner: Wrapper(label.to_string(), Vec::new()),
+            point: Point {
+                x,
+                name: name.to_string(),
+            },
+        })?;
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,68 @@
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+fn main() {
+    let plugins: [&dyn Walkable; 2] = [&Range(5), &Squares(4)];
+
+    assert_eq!(sum_below(plugins[0], 3), 3);
+    assert_eq!(sum_below(plugins[1], 5), 5);
+    assert_eq!(find(plugins[1], 9), Some(3));
+    assert_eq!(find(plugins[1], 10), None);
+}
+
+// Object-safe iterators can't be generic over the break type so they take a `&mut dyn FnMut` with
+// a fixed one instead.
+trait Walkable {
+    fn walk(&self, f: &mut dyn FnMut(u32) -> ControlFlow<()>) -> ControlFlow<()>;
+}
+
+struct Range(u32);
+
+impl Walkable for Range {
+    fn walk(&self, f: &mut dyn FnMut(u32) -> ControlFlow<()>) -> ControlFlow<()> {
+        for i in 0..self.0 {
+            f(i)?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+struct Squares(u32);
+
+impl Walkable for Squares {
+    fn walk(&self, f: &mut dyn FnMut(u32) -> ControlFlow<()>) -> ControlFlow<()> {
+        for i in 0..self.0 {
+            f(i * i)?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+fn sum_below(walkable: &dyn Walkable, max: u32) -> u32 {
+    let mut sum = 0;
+    cbit!(
+        #[dyn_callback]
+        for i in walkable.walk() {
+            if i >= max {
+                break;
+            }
+            sum += i;
+        }
+    );
+    sum
+}
+
+fn find(walkable: &dyn Walkable, target: u32) -> Option<usize> {
+    let mut index = 0;
+    cbit!(
+        #[dyn_callback]
+        for i in walkable.walk() {
+            if i == target {
+                return Some(index);
+            }
+            index += 1;
+        }
+    );
+    None
+}
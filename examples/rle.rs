@@ -0,0 +1,63 @@
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+fn main() {
+    assert_eq!(encode("aaabccdddd"), "a3b1c2d4");
+    assert_eq!(encode(""), "");
+    assert_eq!(longest_run(&[1, 1, 2, 2, 2, 1]), Some((2, 3)));
+    assert_eq!(first_repeated(&[1, 2, 3, 3, 4]), Some(3));
+    assert_eq!(first_repeated(&[1, 2, 3]), None);
+}
+
+fn encode(text: &str) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut out = String::new();
+    cbit!(for (c, count) in rle(&chars) {
+        out.push(*c);
+        out.push_str(&count.to_string());
+    });
+    out
+}
+
+fn longest_run(values: &[i32]) -> Option<(i32, usize)> {
+    let mut longest = None::<(i32, usize)>;
+    cbit!(for (&value, count) in rle(values) {
+        if longest.map_or(true, |(_, longest)| count > longest) {
+            longest = Some((value, count));
+        }
+    });
+    longest
+}
+
+fn first_repeated(values: &[i32]) -> Option<i32> {
+    cbit!(for (value, count) in rle(values) {
+        if count > 1 {
+            return Some(*value);
+        }
+    });
+    None
+}
+
+// Calls `f` with each run of equal elements as a `(value, count)` pair. The run being built up is
+// state carried across elements, which is awkward to express with `Iterator` but trivial here.
+fn rle<'a, T: PartialEq, B>(
+    values: &'a [T],
+    mut f: impl FnMut((&'a T, usize)) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    let mut run = None::<(&'a T, usize)>;
+    for value in values {
+        match &mut run {
+            Some((current, count)) if *current == value => *count += 1,
+            _ => {
+                if let Some(finished) = run.replace((value, 1)) {
+                    f(finished)?;
+                }
+            }
+        }
+    }
+    if let Some(finished) = run {
+        f(finished)?;
+    }
+    ControlFlow::Continue(())
+}
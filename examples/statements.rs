@@ -0,0 +1,99 @@
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+// A `cbit!` loop is an expression, just like a regular `loop`. As a statement, its value is
+// discarded; in any other position, it is the value of the expression.
+fn main() {
+    // Unit results
+    let mut sum = 0;
+    cbit!(for i in up_to(4) {
+        sum += i;
+    });
+    cbit! {
+        for i in up_to(4) {
+            sum += i;
+        }
+    }
+    let () = cbit!(for i in up_to(4) => sum += i);
+    assert_eq!(sum, 18);
+
+    // Non-unit results are discarded by statements...
+    cbit!(for i in search(10) {
+        if i == 3 {
+            break Some(i);
+        }
+    });
+
+    // ...and captured everywhere else.
+    let found = cbit!(for i in search(10) {
+        if i == 3 {
+            break Some(i);
+        }
+    });
+    assert_eq!(found, Some(3));
+
+    let not_found = cbit!(for i in search(10) => if i == 30 { break Some(i) });
+    assert_eq!(not_found, None);
+
+    // The expansion can be used as the receiver of a method call or as an operand.
+    assert!(cbit!(for i in search(10) => if i == 5 { break Some(i) }).is_some());
+    let doubled = cbit!(for i in search(10) => if i == 5 { break Some(i) }).map(|i| i * 2);
+    assert_eq!(doubled, Some(10));
+
+    for which in [true, false] {
+        let value = match which {
+            true => cbit!(for i in search(10) => if i == 7 { break Some(i) }),
+            false => None,
+        };
+        assert_eq!(value, which.then_some(7));
+    }
+
+    assert_eq!(tail_position(4), Some(4));
+    assert_eq!(tail_position(40), None);
+    assert_eq!(returns_from_statement(), 2);
+    assert_eq!(breaks_out_of_statement(), 6);
+}
+
+fn tail_position(needle: u64) -> Option<u64> {
+    cbit!(for i in search(10) {
+        if i == needle {
+            break Some(i);
+        }
+    })
+}
+
+fn returns_from_statement() -> u64 {
+    cbit!(for i in up_to(10) {
+        if i == 2 {
+            return i;
+        }
+    });
+    unreachable!()
+}
+
+fn breaks_out_of_statement() -> u64 {
+    'outer: loop {
+        let mut sum = 0;
+        cbit!(for i in up_to(10) break loop 'outer {
+            if i == 4 {
+                break 'outer sum;
+            }
+            sum += i;
+        });
+    }
+}
+
+fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
+
+fn search<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B, Option<u64>> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(None)
+}
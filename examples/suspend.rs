@@ -0,0 +1,61 @@
+use std::{future, ops::ControlFlow, panic};
+
+use cbit::cbit;
+
+fn main() {
+    panic::set_hook(Box::new(|_| {}));
+    let mut loop_line = 0;
+    let payload = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        loop_line = line!() + 1;
+        cbit!(for i in up_to(3) {
+            if i == 1 {
+                // Never resolves, so the body gets suspended.
+                future::pending::<()>().await;
+            }
+        });
+    }))
+    .unwrap_err();
+    let _ = panic::take_hook();
+
+    let message = payload.downcast_ref::<String>().unwrap();
+    let location = format!("at {}:{loop_line}", file!());
+    assert!(message.contains(&location), "{message}");
+    assert!(
+        message.contains("was suspended by an `.await`"),
+        "{message}"
+    );
+    assert!(message.contains("remove the `.await`"), "{message}");
+
+    // A custom handler replaces the default panic.
+    panic::set_hook(Box::new(|_| {}));
+    let payload = panic::catch_unwind(|| {
+        cbit!(
+            #[on_await_misuse(|| panic!("custom handler"))]
+            for i in up_to(3) {
+                if i == 1 {
+                    future::pending::<()>().await;
+                }
+            }
+        );
+    })
+    .unwrap_err();
+    let _ = panic::take_hook();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"custom handler"));
+
+    // Futures which are immediately ready never reach the handler.
+    let mut sum = 0;
+    cbit!(
+        #[on_await_misuse(|| unreachable!())]
+        for i in up_to(3) {
+            sum += future::ready(i).await;
+        }
+    );
+    assert_eq!(sum, 3);
+}
+
+fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+    for i in 0..n {
+        f(i)?;
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,45 @@
+use std::{ops::ControlFlow, thread};
+
+use cbit::cbit;
+
+fn main() {
+    let mut seen = Vec::new();
+    cbit!(for i in on_worker(10) {
+        if i == 4 {
+            break;
+        }
+        seen.push(i);
+    });
+    assert_eq!(seen, [0, 1, 2, 3]);
+
+    assert_eq!(first_multiple(7, 3), Some(3));
+    assert_eq!(first_multiple(2, 3), None);
+}
+
+fn first_multiple(n: u32, of: u32) -> Option<u32> {
+    cbit!(for i in on_worker(n) {
+        if i > 0 && i % of == 0 {
+            return Some(i);
+        }
+    });
+    None
+}
+
+// The generated closure is `Send` as long as everything it captures is, so it can be driven from
+// another thread.
+fn on_worker<B, F>(n: u32, mut f: F) -> ControlFlow<B>
+where
+    B: Send,
+    F: FnMut(u32) -> ControlFlow<B> + Send,
+{
+    thread::scope(|s| {
+        s.spawn(move || {
+            for i in 0..n {
+                f(i)?;
+            }
+            ControlFlow::Continue(())
+        })
+        .join()
+        .unwrap()
+    })
+}
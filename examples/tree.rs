@@ -0,0 +1,133 @@
+use std::ops::ControlFlow;
+
+use cbit::cbit;
+
+fn main() {
+    let tree = Node::new(
+        1,
+        vec![
+            Node::new(2, vec![Node::leaf(4), Node::new(5, vec![Node::leaf(8)])]),
+            Node::new(3, vec![Node::leaf(6), Node::new(7, vec![Node::leaf(9)])]),
+        ],
+    );
+
+    assert_eq!(preorder(&tree), [1, 2, 4, 5, 8, 3, 6, 7, 9]);
+    assert_eq!(odd_sum(&tree), 1 + 5 + 3 + 7 + 9);
+    assert_eq!(find_deep(&tree, 8), Some(3));
+    assert_eq!(find_deep(&tree, 10), None);
+    assert_eq!(first_above(&tree, 6), Some(8));
+    assert_eq!(count_before(&[&tree, &tree], 5), (2, 6));
+    assert_eq!(depth_sums(&tree), [1, 5, 22, 17]);
+}
+
+// The callback is called from deep within the recursion but never while another call to it is
+// still running so the body's per-call state is set up anew each time.
+fn preorder(tree: &Node) -> Vec<u32> {
+    let mut seen = Vec::new();
+    cbit!(for (node, _depth) in walk(tree) {
+        seen.push(node.value);
+    });
+    seen
+}
+
+fn odd_sum(tree: &Node) -> u32 {
+    let mut sum = 0;
+    cbit!(for (node, _depth) in walk(tree) {
+        if node.value % 2 == 0 {
+            continue;
+        }
+        sum += node.value;
+    });
+    sum
+}
+
+// Breaking from deep within the recursion unwinds every level of it.
+fn find_deep(tree: &Node, value: u32) -> Option<usize> {
+    'found: {
+        cbit!(for (node, depth) in walk(tree) break out 'found {
+            if node.value == value {
+                break out Some(depth);
+            }
+        });
+        None
+    }
+}
+
+fn first_above(tree: &Node, min: u32) -> Option<u32> {
+    cbit!(for (node, _depth) in walk(tree) {
+        if node.value > min {
+            return Some(node.value);
+        }
+    });
+    None
+}
+
+// Continuing an outer loop from deep within the recursion.
+fn count_before(trees: &[&Node], stop: u32) -> (usize, usize) {
+    let mut trees_seen = 0;
+    let mut nodes_seen = 0;
+    'trees: for tree in trees {
+        trees_seen += 1;
+        cbit!(for (node, _depth) in walk(tree) break for 'trees {
+            if node.value == stop {
+                continue 'trees;
+            }
+            nodes_seen += 1;
+        });
+    }
+    (trees_seen, nodes_seen)
+}
+
+// Nested loops over the same tree, each with its own recursion.
+fn depth_sums(tree: &Node) -> Vec<u32> {
+    let mut sums = Vec::new();
+    cbit!('depths: for (_, depth) in walk(tree) {
+        if depth < sums.len() {
+            continue 'depths;
+        }
+
+        let mut sum = 0;
+        cbit!(for (node, other_depth) in walk(tree) {
+            if other_depth == depth {
+                sum += node.value;
+            }
+        });
+        sums.push(sum);
+    });
+    sums
+}
+
+struct Node {
+    value: u32,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(value: u32, children: Vec<Node>) -> Self {
+        Self { value, children }
+    }
+
+    fn leaf(value: u32) -> Self {
+        Self::new(value, Vec::new())
+    }
+}
+
+// Calls `f` with every node of the tree in pre-order along with its depth.
+fn walk<'a, B>(
+    tree: &'a Node,
+    mut f: impl FnMut((&'a Node, usize)) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    fn walk_inner<'a, B>(
+        node: &'a Node,
+        depth: usize,
+        f: &mut impl FnMut((&'a Node, usize)) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        f((node, depth))?;
+        for child in &node.children {
+            walk_inner(child, depth + 1, f)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    walk_inner(tree, 0, &mut f)
+}
@@ -0,0 +1,501 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Expr, ExprClosure, Label, Pat};
+
+use crate::{
+    expand_cbit,
+    syntax::{
+        AnyCallExpr, CallAndFn, CallInitAndFn, EnumerateArgs, ForIterArgs, ForRangeArgs,
+        WindowsArgs, ZipArgs,
+    },
+};
+
+/// Produces a `(prelude, invocation)` pair which calls the user-supplied `func` with `args`.
+///
+/// Closure literals are passed through a generic trampoline at every call site so that their
+/// argument types can be inferred from the arguments (a closure bound to a local ahead of time
+/// would have no expected signature). Everything else is evaluated exactly once in the prelude.
+fn call_user_fn(func: &Expr, args: &[&Ident]) -> (TokenStream, TokenStream) {
+    let apply = Ident::new("apply", Span::mixed_site());
+
+    if let Expr::Closure(_) = func {
+        let arg_tys = (0..args.len())
+            .map(|i| Ident::new(&format!("A{i}"), Span::mixed_site()))
+            .collect::<Vec<_>>();
+
+        let prelude = quote! {
+            #[inline(always)]
+            fn #apply<#(#arg_tys,)* R>(
+                f: impl ::core::ops::FnOnce(#(#arg_tys),*) -> R,
+                #(#args: #arg_tys,)*
+            ) -> R {
+                f(#(#args),*)
+            }
+        };
+
+        (prelude, quote! { #apply(#func, #(#args),*) })
+    } else {
+        let func_var = Ident::new("func", Span::mixed_site());
+
+        (
+            quote! { let mut #func_var = #func; },
+            quote! { #func_var(#(#args),*) },
+        )
+    }
+}
+
+pub fn find_map(input: CallAndFn) -> syn::Result<TokenStream> {
+    let elem = Ident::new("elem", Span::mixed_site());
+    let found = Ident::new("found", Span::mixed_site());
+    let mapped = Ident::new("mapped", Span::mixed_site());
+    let option_ = quote! { ::core::option::Option };
+
+    let (prelude, invocation) = call_user_fn(&input.func, &[&elem]);
+    let call = &input.call;
+    let driver = expand_cbit(parse_quote! {
+        for #elem in #call {
+            if let #option_::Some(#mapped) = #invocation {
+                #found = #option_::Some(#mapped);
+                break;
+            }
+        }
+    })?;
+
+    Ok(quote! {{
+        #prelude
+        let mut #found = #option_::None;
+        #driver;
+        #found
+    }})
+}
+
+pub fn position(input: CallAndFn) -> syn::Result<TokenStream> {
+    let elem = Ident::new("elem", Span::mixed_site());
+    let index = Ident::new("index", Span::mixed_site());
+    let found = Ident::new("found", Span::mixed_site());
+    let option_ = quote! { ::core::option::Option };
+
+    let (prelude, invocation) = call_user_fn(&input.func, &[&elem]);
+    let call = &input.call;
+    let driver = expand_cbit(parse_quote! {
+        for #elem in #call {
+            if #invocation {
+                #found = #option_::Some(#index);
+                break;
+            }
+            #index += 1;
+        }
+    })?;
+
+    Ok(quote! {{
+        #prelude
+        let mut #index: usize = 0;
+        let mut #found = #option_::None;
+        #driver;
+        #found
+    }})
+}
+
+pub fn fold(input: CallInitAndFn) -> syn::Result<TokenStream> {
+    let elem = Ident::new("elem", Span::mixed_site());
+    let accum = Ident::new("accum", Span::mixed_site());
+    let prev = Ident::new("prev", Span::mixed_site());
+    let option_ = quote! { ::core::option::Option };
+
+    // The accumulator is moved into the user's function on every iteration, which the loop's
+    // closure can only do to a captured variable by taking it out of an `Option`.
+    let (prelude, invocation) = call_user_fn(&input.func, &[&prev, &elem]);
+    let call = &input.call;
+    let init = &input.init;
+    let driver = expand_cbit(parse_quote! {
+        for #elem in #call {
+            let #prev = #accum.take().unwrap();
+            #accum = #option_::Some(#invocation);
+        }
+    })?;
+
+    Ok(quote! {{
+        #prelude
+        let mut #accum = #option_::Some(#init);
+        #driver;
+        #accum.unwrap()
+    }})
+}
+
+pub fn try_fold(input: CallInitAndFn) -> syn::Result<TokenStream> {
+    let elem = Ident::new("elem", Span::mixed_site());
+    let accum = Ident::new("accum", Span::mixed_site());
+    let prev = Ident::new("prev", Span::mixed_site());
+    let next = Ident::new("next", Span::mixed_site());
+    let error = Ident::new("error", Span::mixed_site());
+    let error_slot = Ident::new("error_slot", Span::mixed_site());
+    let option_ = quote! { ::core::option::Option };
+    let result_ = quote! { ::core::result::Result };
+
+    // The first error is stashed away rather than broken out with since the loop would otherwise
+    // have to evaluate to the same type when the iterator completes.
+    let (prelude, invocation) = call_user_fn(&input.func, &[&prev, &elem]);
+    let call = &input.call;
+    let init = &input.init;
+    let driver = expand_cbit(parse_quote! {
+        for #elem in #call {
+            let #prev = #accum.take().unwrap();
+            match #invocation {
+                #result_::Ok(#next) => #accum = #option_::Some(#next),
+                #result_::Err(#error) => {
+                    #error_slot = #option_::Some(#error);
+                    break;
+                }
+            }
+        }
+    })?;
+
+    Ok(quote! {{
+        #prelude
+        let mut #accum = #option_::Some(#init);
+        let mut #error_slot = #option_::None;
+        #driver;
+        match #error_slot {
+            #option_::Some(#error) => #result_::Err(#error),
+            #option_::None => #result_::Ok(#accum.unwrap()),
+        }
+    }})
+}
+
+pub fn try_drive(input: CallAndFn) -> syn::Result<TokenStream> {
+    let elem = Ident::new("elem", Span::mixed_site());
+    let value = Ident::new("value", Span::mixed_site());
+    let error = Ident::new("error", Span::mixed_site());
+    let control_flow_ = quote! { ::core::ops::ControlFlow };
+    let result_ = quote! { ::core::result::Result };
+
+    // Unlike the other helpers, this doesn't go through `cbit!` since the iterator is allowed to
+    // break with a concrete error type of its own rather than a generic one.
+    let (prelude, invocation) = call_user_fn(&input.func, &[&elem]);
+    let mut call = input.call;
+    call.args_mut().push(parse_quote! {
+        |#elem| match #invocation {
+            #result_::Ok(()) => #control_flow_::Continue(()),
+            #result_::Err(#error) => #control_flow_::Break(#error),
+        }
+    });
+
+    Ok(quote! {{
+        #prelude
+        match #call {
+            #control_flow_::Continue(#value) => #result_::Ok(#value),
+            #control_flow_::Break(#error) => #result_::Err(#error),
+        }
+    }})
+}
+
+pub fn for_range(input: ForRangeArgs) -> syn::Result<TokenStream> {
+    let range = &input.range;
+    let range = match &input.step {
+        Some((_, step, _)) => quote! { ::core::iter::Iterator::step_by(#range, #step) },
+        None => quote! { #range },
+    };
+
+    builtin_loop(range, &input.closure)
+}
+
+pub fn enumerate(input: EnumerateArgs) -> syn::Result<TokenStream> {
+    let iter = &input.iter;
+    let iter_ = quote! { ::core::iter };
+
+    builtin_loop(
+        quote! { #iter_::Iterator::enumerate(#iter_::IntoIterator::into_iter(#iter)) },
+        &input.closure,
+    )
+}
+
+pub fn zip(input: ZipArgs) -> syn::Result<TokenStream> {
+    let (left, right) = (&input.left, &input.right);
+    let iter_ = quote! { ::core::iter };
+
+    builtin_loop(
+        quote! { #iter_::Iterator::zip(#iter_::IntoIterator::into_iter(#left), #right) },
+        &input.closure,
+    )
+}
+
+pub fn windows(input: WindowsArgs) -> syn::Result<TokenStream> {
+    let window = Ident::new("window", Span::mixed_site());
+    let (slice, size) = (&input.slice, &input.size);
+
+    // `windows` only produces slices so each one is converted to an array reference, which can't
+    // fail because every window has exactly `size` elements.
+    builtin_loop(
+        quote! {
+            ::core::iter::Iterator::map(<[_]>::windows(#slice, #size), |#window| {
+                match <&[_; #size] as ::core::convert::TryFrom<_>>::try_from(#window) {
+                    ::core::result::Result::Ok(#window) => #window,
+                    ::core::result::Result::Err(_) => ::core::unreachable!(),
+                }
+            })
+        },
+        &input.closure,
+    )
+}
+
+pub fn chunks(input: WindowsArgs) -> syn::Result<TokenStream> {
+    let (slice, size) = (&input.slice, &input.size);
+
+    builtin_loop(quote! { <[_]>::chunks(#slice, #size) }, &input.closure)
+}
+
+/// Expands a `cbit!` loop over any `IntoIterator` with a closure-like body.
+fn builtin_loop(iter: TokenStream, closure: &ExprClosure) -> syn::Result<TokenStream> {
+    let binding = match &closure.inputs[0] {
+        Pat::Type(pat) => {
+            let (inner, colon, ty) = (&pat.pat, &pat.colon_token, &pat.ty);
+            quote! { #inner #colon #ty }
+        }
+        pat => quote! { #pat },
+    };
+
+    // Block bodies are spliced in directly rather than being nested in a second block.
+    let body = match &*closure.body {
+        Expr::Block(block) if block.attrs.is_empty() && block.label.is_none() => {
+            let stmts = &block.block.stmts;
+            quote! { #(#stmts)* }
+        }
+        body => quote! { #body },
+    };
+
+    iter_loop(None, binding, iter, body)
+}
+
+/// Expands a `cbit!` loop over the elements of `iter` using a local driver function.
+fn iter_loop(
+    label: Option<&Label>,
+    binding: TokenStream,
+    iter: TokenStream,
+    body: TokenStream,
+) -> syn::Result<TokenStream> {
+    let driver = Ident::new("iter_driver", Span::mixed_site());
+    let ops_ = quote! { ::core::ops };
+
+    let loop_ = expand_cbit(parse_quote! {
+        #label for #binding in #driver(#iter) {
+            #body
+        }
+    })?;
+
+    Ok(quote! {{
+        #[inline(always)]
+        fn #driver<I: ::core::iter::IntoIterator, B>(
+            iter: I,
+            mut f: impl #ops_::FnMut(I::Item) -> #ops_::ControlFlow<B>,
+        ) -> #ops_::ControlFlow<B> {
+            for elem in iter {
+                f(elem)?;
+            }
+            #ops_::ControlFlow::Continue(())
+        }
+
+        #loop_
+    }})
+}
+
+pub fn for_iter(input: ForIterArgs) -> syn::Result<TokenStream> {
+    let binding = &input.binding;
+    let iter = &input.iter;
+    let stmts = &input.body.stmts;
+
+    iter_loop(
+        input.label.as_ref(),
+        quote! { #binding },
+        quote! { #iter },
+        quote! { #(#stmts)* },
+    )
+}
+
+/// The fold applied by [`fold_numeric`].
+pub enum NumericFold {
+    Sum,
+    Product,
+}
+
+pub fn fold_numeric(call: AnyCallExpr, fold: NumericFold) -> syn::Result<TokenStream> {
+    let elem = Ident::new("elem", Span::mixed_site());
+    let accum = Ident::new("accum", Span::mixed_site());
+    let identity = Ident::new("identity", Span::mixed_site());
+    let option_ = quote! { ::core::option::Option };
+
+    // The accumulator starts out as `None` so that its type is inferred from the elements rather
+    // than from the identity, which can only be produced once that type is known.
+    let (identity_def, combine) = match fold {
+        NumericFold::Sum => (
+            quote! {
+                fn #identity<T: ::core::iter::Sum>() -> T {
+                    ::core::iter::Sum::sum(::core::iter::empty::<T>())
+                }
+            },
+            quote! { #accum + #elem },
+        ),
+        NumericFold::Product => (
+            quote! {
+                fn #identity<T: ::core::iter::Product>() -> T {
+                    ::core::iter::Product::product(::core::iter::empty::<T>())
+                }
+            },
+            quote! { #accum * #elem },
+        ),
+    };
+
+    let driver = expand_cbit(parse_quote! {
+        for #elem in #call {
+            #accum = #option_::Some(match #accum {
+                #option_::Some(#accum) => #combine,
+                #option_::None => #elem,
+            });
+        }
+    })?;
+
+    Ok(quote! {{
+        #identity_def
+        let mut #accum = #option_::None;
+        #driver;
+        #accum.unwrap_or_else(#identity)
+    }})
+}
+
+#[cfg(feature = "std")]
+pub fn collect(call: AnyCallExpr) -> syn::Result<TokenStream> {
+    let elem = Ident::new("elem", Span::mixed_site());
+    let collected = Ident::new("collected", Span::mixed_site());
+
+    let driver = expand_cbit(parse_quote! {
+        for #elem in #call {
+            #collected.push(#elem);
+        }
+    })?;
+
+    Ok(quote! {{
+        let mut #collected = ::std::vec::Vec::new();
+        #driver;
+        #collected
+    }})
+}
+
+#[cfg(feature = "rayon")]
+pub fn par_collect(call: AnyCallExpr) -> syn::Result<TokenStream> {
+    let collected = collect(call)?;
+
+    Ok(quote! {
+        ::rayon::iter::IntoParallelIterator::into_par_iter(#collected)
+    })
+}
+
+#[cfg(feature = "std")]
+pub fn timed(call: AnyCallExpr) -> syn::Result<TokenStream> {
+    let count = Ident::new("count", Span::mixed_site());
+    let start = Ident::new("start", Span::mixed_site());
+
+    let driver = expand_cbit(parse_quote! {
+        for _ in #call {
+            #count += 1;
+        }
+    })?;
+
+    Ok(quote! {{
+        let mut #count: usize = 0;
+        let #start = ::std::time::Instant::now();
+        #driver;
+        (#count, #start.elapsed())
+    }})
+}
+
+pub fn stats(call: AnyCallExpr) -> syn::Result<TokenStream> {
+    let count = Ident::new("count", Span::mixed_site());
+    let first = Ident::new("first", Span::mixed_site());
+    let last = Ident::new("last", Span::mixed_site());
+    let elem = Ident::new("elem", Span::mixed_site());
+    let option_ = quote! { ::core::option::Option };
+
+    // Only the first element is cloned since every later one is moved into `last` as it arrives.
+    let driver = expand_cbit(parse_quote! {
+        for #elem in #call {
+            if #option_::is_none(&#first) {
+                #first = #option_::Some(::core::clone::Clone::clone(&#elem));
+            }
+            #last = #option_::Some(#elem);
+            #count += 1;
+        }
+    })?;
+
+    Ok(quote! {{
+        let mut #count: usize = 0;
+        let mut #first = #option_::None;
+        let mut #last = #option_::None;
+        #driver;
+        (#count, #first, #last)
+    }})
+}
+
+/// The element kept by [`best_by`].
+pub enum BestBy {
+    Min,
+    Max,
+}
+
+pub fn best_by(input: CallAndFn, best_by: BestBy) -> syn::Result<TokenStream> {
+    let elem = Ident::new("elem", Span::mixed_site());
+    let best = Ident::new("best", Span::mixed_site());
+    let current = Ident::new("current", Span::mixed_site());
+    let current_ref = Ident::new("current_ref", Span::mixed_site());
+    let elem_ref = Ident::new("elem_ref", Span::mixed_site());
+    let option_ = quote! { ::core::option::Option };
+    let ordering_ = quote! { ::core::cmp::Ordering };
+
+    // Ties are broken like `Iterator::min_by` and `Iterator::max_by` do: the first minimum and the
+    // last maximum win.
+    let replace = match best_by {
+        BestBy::Min => quote! { #ordering_::Greater },
+        BestBy::Max => quote! { #ordering_::Less | #ordering_::Equal },
+    };
+
+    let (prelude, invocation) = call_user_fn(&input.func, &[&current_ref, &elem_ref]);
+    let call = &input.call;
+    // The element is stored before comparing so that the type of `best` is known by the time the
+    // comparator's arguments are.
+    let driver = expand_cbit(parse_quote! {
+        for #elem in #call {
+            if let #option_::Some(#current) = #best.replace(#elem) {
+                if let #option_::Some(#elem_ref) = &#best {
+                    let #current_ref = &#current;
+                    match #invocation {
+                        #replace => {}
+                        _ => #best = #option_::Some(#current),
+                    }
+                }
+            }
+        }
+    })?;
+
+    Ok(quote! {{
+        #prelude
+        let mut #best = #option_::None;
+        #driver;
+        #best
+    }})
+}
+
+pub fn last(call: AnyCallExpr) -> syn::Result<TokenStream> {
+    let last = Ident::new("last", Span::mixed_site());
+    let elem = Ident::new("elem", Span::mixed_site());
+    let option_ = quote! { ::core::option::Option };
+
+    let driver = expand_cbit(parse_quote! {
+        for #elem in #call {
+            #last = #option_::Some(#elem);
+        }
+    })?;
+
+    Ok(quote! {{
+        let mut #last = #option_::None;
+        #driver;
+        #last
+    }})
+}
@@ -286,12 +286,69 @@
 //! However, what more general coroutine implementations provide in exchange for potential performance
 //! degradation is immense expressivity. Fundamentally, `cbit` iterators cannot be interwoven, making
 //! adapters such as `zip` impossible to implement—something coroutines have no problem doing.
+//!
+//! Finally, since the body of a `cbit` loop is a closure which may run many times, it cannot move
+//! out of the variables it captures, including `self`. Regular loops allow this right before a
+//! `return` or `break` but, with `cbit`, such moves must happen after the loop instead:
+//!
+//! ```
+//! # use std::ops::ControlFlow;
+//! # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+//! #     for i in 0..n {
+//! #         f(i)?;
+//! #     }
+//! #     ControlFlow::Continue(())
+//! # }
+//! fn take_if_found(name: String, needle: u64) -> Option<String> {
+//!     let mut found = false;
+//!     cbit::cbit!(for i in up_to(10) {
+//!         if i == needle {
+//!             found = true;  // `return Some(name)` would be an error here.
+//!             break;
+//!         }
+//!     });
+//!     found.then_some(name)
+//! }
+//!
+//! assert_eq!(take_if_found("a".to_string(), 3), Some("a".to_string()));
+//! assert_eq!(take_if_found("a".to_string(), 30), None);
+//! ```
+//!
+//! ## `no_std` Support
+//!
+//! The expansion of `cbit!` only refers to `core` and never allocates, so loops can be used in
+//! `no_std` crates, including those without a global allocator. The body's state is kept in a pinned
+//! future on the stack, so the only cost is that stack space. The exceptions are the opt-in `#[trace]`
+//! option, which logs through `std`, and the helper macros which build collections or measure time
+//! (`collect!`, `par_collect!`, and `timed!`).
+//!
+//! ## Feature Flags
+//!
+//! - `legacy-waker` (enabled by default): polls loop bodies with a hand-rolled no-op waker instead
+//!   of [`Waker::noop`](::std::task::Waker::noop), allowing the expansion to compile on toolchains
+//!   older than Rust 1.85. This makes the expansion contain an `unsafe` block and drops the custom
+//!   error message for iterators which don't return a `ControlFlow`, which requires Rust 1.78. Crates
+//!   which only target Rust 1.85 or newer can disable default features (keeping `std` if they need
+//!   it) to get the newer expansion.
+//! - `trace`: enables the `#[trace]` loop option, which logs the control-flow path each loop exits
+//!   through to stderr.
+//! - `std` (enabled by default): enables the `collect!` macro, which gathers elements into a `Vec`,
+//!   and the `timed!` macro, which measures iterators using [`std::time`](::std::time).
+//! - `rayon`: enables the `par_collect!` macro, which collects the elements of a cbit iterator and
+//!   hands them to `rayon` as a parallel iterator. This implies `std`.
 
 use proc_macro2::{Ident, Span, TokenStream};
-use quote::quote;
-use syn::{punctuated::Punctuated, Lifetime, Token};
-use syntax::CbitForExpr;
+use quote::{quote, quote_spanned};
+use scan::BodyInfo;
+use syn::{punctuated::Punctuated, spanned::Spanned, Label, Lifetime, Token};
+use syntax::{
+    AnyCallExpr, CallAndFn, CallInitAndFn, CbitForExpr, CbitForExprBreaks, CbitForExprSingleBreak,
+    CbitOptions, EnumerateArgs, ForIterArgs, ForRangeArgs, WindowsArgs, ZipArgs,
+};
 
+mod helpers;
+mod rewrite;
+mod scan;
 mod syntax;
 
 /// A proc-macro to use callback-based iterators with for-loop syntax and functionality.
@@ -299,101 +356,3048 @@ mod syntax;
 /// ## Syntax
 ///
 /// ```text
-/// ('<loop-label: lifetime>:)? for <binding: pattern> in <iterator: function-call-expr>
-///     (break ((loop)? '<extern-label: lifetime>)*)?
-/// {
+/// (#[<option>])*
+/// (raw <raw-result: ident> =>)?
+/// (::<<element-type: type>>)?
+/// (try)?
+/// ('<loop-label: lifetime>:)? (do)? for (<'<binder: lifetime>,*>)?
+///     (<binding: pattern | assignee-expr> (: <element-type: type>)?
+///     (first(<first-flag: ident>))?)?
+///     in <iterator: function-call-expr>
+///     (then <chained-iterator: function-call-expr>)*
+///     (unwrap(<adapter: expr>))?
+///     (-> <result-type: type>)?
+///     (break ((out | loop | while | for)? '<extern-label: lifetime> (with <continue-target: assignee-expr>)?)*)?
+/// ({
 ///     <body: token stream>
-/// }
+/// } | => <body: expr> | @body <body-macro: macro-invocation>)
+/// (while <do-condition: expr>)?
+/// (; <raw-handler: expr>)?
 /// ```
 ///
 /// Arguments:
 ///
+/// - `option`: An option configuring the expansion. See the [options](#options) section.
+/// - `raw-result` and `raw-handler`: An optional name for the unmatched result of the loop and the
+///   expression handling it in its stead. See the [raw results](#raw-results) section.
+/// - `element-type`: The optional type of the elements passed to the body. This can either be given
+///   as a leading turbofish or after the binding, but not both.
+/// - `try`: Propagates the values the loop is broken out of with using `?`. See the
+///   [try mode](#try-mode) section.
 /// - `loop-label`: This is the optional label used by your virtual loop. `break`'ing or `continue`'ing
 ///   to this label will break out of and continue the cbit iterator respectively.
+/// - `do` and `do-condition`: Makes the loop check a condition after each run of its body. See the
+///   [do-while loops](#do-while-loops) section.
+/// - `binder`: Optional names for the lifetimes used by `element-type`. See the
+///   [bindings](#bindings) section.
 /// - `binding`: This is the irrefutable pattern the iterator's arguments will be decomposed into.
+///   See the [bindings](#bindings) section for the other forms this can take. Omitting it is
+///   exactly equivalent to writing `_`.
+/// - `first-flag`: An optional name for a `bool` which is `true` during the first run of the body
+///   and `false` during every other. See the [bindings](#bindings) section.
 /// - `iterator`: Syntactically, this can be any (potentially generic) function or method call
 ///   expression and generics can be explicitly supplied if desired. See the [iteration protocol](#iteration-protocol)
 ///   section for details on the semantic requirements for this function.
+/// - `chained-iterator`: Further iterators run with the same body once the previous one completes.
+///   See the [chained iterators](#chained-iterators) section.
+/// - `adapter`: An optional function converting the value returned by the iterator into a
+///   `ControlFlow` object for iterators which wrap their return value. See the
+///   [iteration protocol](#iteration-protocol) section.
+/// - `result-type`: The optional type of the value the loop evaluates to. See the
+///   [syntax](#syntax) section.
 /// - The loop also contains an optional list of external control-flow labels which is started by the
 ///   `break` keyword and is followed by a non-empty non-trailing comma-separated list of...
 ///      - An optional `loop` keyword which, if specified, asserts that the label can accept `continue`s
-///        in addition to `break`s.
+///        in addition to `break`s. Labels of `while`, `while let`, and `for` loops must be declared
+///        with the `while` or `for` keyword instead since those loops cannot be broken out of with a
+///        value.
+///      - Alternatively, an optional `out` keyword which, if specified, allows the body to break out
+///        of the labeled block with `break out` instead of naming it. At most one label can be
+///        declared this way.
 ///      - `extern-label`: the label the `cbit!` body is allowed to `break` or `continue` out to.
+///      - An optional `with` keyword followed by an assignee expression which, if specified, allows
+///        the body to continue the `loop` label with a value. See the [external labels](#external-labels)
+///        section.
+/// - `body`: The body of the loop. Instead of a block, this can be a single expression introduced
+///   by `=>`, which is exactly equivalent to wrapping that expression in braces. The body can use
+///   `redo with <value>` to run itself again. See the [redoing elements](#redoing-elements) section.
+/// - `body-macro`: A macro invocation expanding to the entire body. See the
+///   [generated bodies](#generated-bodies) section.
 ///
-/// ## Iteration Protocol
+/// Unlike a regular `for` loop, forgetting the body is reported as such rather than as a generic
+/// parse error.
 ///
-/// The called function or method can take on any non-zero number of arguments but must accept a
-/// single-argument function closure as its last argument. The closure must be able to return a
-/// [`ControlFlow`](::std::ops::ControlFlow) object with a generic `Break` type and the function must
-/// return a `ControlFlow` object with the same `Break` type.
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// cbit::cbit!(for i in up_to(4));  // error: a `cbit!` loop requires a `{ ... }` or `=> <expr>` body
+/// ```
+///
+/// The `=>` form comes last, after the `break` clause if there is one, and its expression extends
+/// to the end of the invocation. It supports the same control flow as a block body.
 ///
 /// ```
-/// use std::{iter::IntoIterator, ops::ControlFlow};
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut seen = Vec::new();
+/// cbit::cbit!(for i in up_to(3) => seen.push(i));
+/// assert_eq!(seen, [0, 1, 2]);
 ///
-/// // A simple example...
-/// fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
-///     for i in 0..n {
-///         f(i)?;
+/// let found = 'found: {
+///     cbit::cbit!(for i in up_to(10) break 'found => if i * i > 10 { break 'found Some(i) });
+///     None
+/// };
+/// assert_eq!(found, Some(4));
+/// ```
+///
+/// Multiple statements still require a block.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut seen = Vec::new();
+/// cbit::cbit!(for i in up_to(3) => seen.push(i); seen.push(i));  // error: ...single expression
+/// ```
+///
+/// The iterator must be written out as a call. Naming the iterator function without calling it,
+/// passing a closure which calls it, or producing the call with a macro are each rejected with an
+/// error explaining what to write instead.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// cbit::cbit!(for i in up_to {  // error: ...add `(...)` with the iterator's arguments to call it
+///     println!("{i}");
+/// });
+/// ```
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// cbit::cbit!(for i in |f| up_to(3, f) {  // error: ...not a closure
+///     println!("{i}");
+/// });
+/// ```
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// macro_rules! three {
+///     () => { up_to(3) };
+/// }
+///
+/// cbit::cbit!(for i in three!() {  // error: ...macro invocations are not expanded
+///     println!("{i}");
+/// });
+/// ```
+///
+/// Attributes on the iterator call are applied to the statement making the call. `#[cfg(...)]`
+/// attributes, however, apply to the entire loop so that a configured-away call removes the loop
+/// altogether. Such loops must evaluate to `()`.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut sum = 0;
+///
+/// cbit::cbit!(for i in #[cfg(all())] up_to(4) {
+///     sum += i;
+/// });
+///
+/// cbit::cbit!(for i in #[cfg(any())] up_to(100) {
+///     sum += i;
+/// });
+///
+/// assert_eq!(sum, 6);
+/// ```
+///
+/// A `cbit!` loop used as a statement can also be gated like any other statement. The attribute is
+/// evaluated before the macro is expanded so a configured-away loop is never expanded at all and
+/// may even refer to items which don't exist in that configuration.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut sum = 0;
+///
+/// #[cfg(all())]
+/// cbit::cbit!(for i in up_to(4) {
+///     sum += i;
+/// });
+///
+/// #[cfg(any())]
+/// cbit::cbit!(for i in does_not_exist(100) {
+///     sum += i;
+/// });
+///
+/// assert_eq!(sum, 6);
+/// ```
+///
+/// Like a regular `loop`, a `cbit!` loop is an expression. Followed by a semicolon, its value is
+/// discarded (and `#[must_use]` values are linted as such) while anywhere else, it evaluates to it.
+/// A brace-delimited invocation used as a statement without a semicolon must evaluate to `()`, just
+/// as a `loop { ... }` statement would.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn search<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B, Option<u64>> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(None)
+/// # }
+/// cbit::cbit!(for i in search(10) => if i == 3 { break Some(i) });
+///
+/// let found = cbit::cbit!(for i in search(10) => if i == 3 { break Some(i) });
+/// assert_eq!(found, Some(3));
+/// ```
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn search<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B, Option<u64>> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(None)
+/// # }
+/// cbit::cbit! {
+///     for i in search(10) => if i == 3 { break Some(i) }  // error: expected `()`
+/// }
+/// ```
+///
+/// The type the loop evaluates to can be spelled out with `-> <type>` after the iterator call. Like
+/// a `let` with a type annotation, this documents the type of the loop's value and drives
+/// inference of the `break` values and of the iterator's completion value, both of which must have
+/// that type. In [try mode](#try-mode), it is the type of the unwrapped values. Only the loop's
+/// head can hold the annotation, so a `->` following the body, such as that of a closure returned by
+/// a [`raw`](#raw-results) handler, is left alone.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn search<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B, Option<u64>> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(None)
+/// # }
+/// let found = cbit::cbit!(for i in search(10) -> Option<u64> {
+///     if i * i > 20 {
+///         break Some(i);
+///     }
+/// });
+/// assert_eq!(found, Some(5));
+///
+/// // Completes with `T::default()` for any `T`.
+/// fn words<'a, B, T: Default>(text: &'a str, mut f: impl FnMut(&'a str) -> ControlFlow<B>) -> ControlFlow<B, T> {
+///     for word in text.split_whitespace() {
+///         f(word)?;
+///     }
+///     ControlFlow::Continue(T::default())
+/// }
+///
+/// // Nothing else says what `parse` should produce.
+/// let number = cbit::cbit!(for word in words("no 42 here") -> Option<u8> {
+///     if let Ok(number) = word.parse() {
+///         break Some(number);
+///     }
+/// });
+/// assert_eq!(number, Some(42));
+///
+/// let is_break = cbit::cbit!(raw result => for i in search(10) {
+///     if i == 3 {
+///         break;
+///     }
+/// }; move || -> bool { result.is_break() });
+/// assert!(is_break());
+/// ```
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn search<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B, Option<u64>> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(None)
+/// # }
+/// let found = cbit::cbit!(for i in search(10) -> Option<u32> {  // error: expected `Option<u32>`, found `Option<u64>`
+///     if i * i > 20 {
+///         break Some(i);
+///     }
+/// });
+/// ```
+///
+/// ## Chained Iterators
+///
+/// Iterators can't be interleaved but they can be run one after another. Each iterator chained with
+/// `then` runs the same body once the previous one completes, in which case the previous one's
+/// completion value is discarded and the loop evaluates to the one of the last iterator. Breaking
+/// out of the loop, or returning from the body, skips the remaining iterators.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// fn squares<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 1..=n {
+///         f(i * i)?;
 ///     }
 ///     ControlFlow::Continue(())
 /// }
 ///
-/// // A slightly more involved example...
-/// fn enumerate<I: IntoIterator, B>(
-///     values: I,
-///     index_offset: usize,
-///     mut f: impl FnMut((usize, I::Item),
-/// ) -> ControlFlow<B>) -> ControlFlow<B> {
-///     for (i, v) in values.into_iter().enumerate() {
-///         f((i + index_offset, v))?;
+/// let mut seen = Vec::new();
+/// cbit::cbit!(for i in up_to(3) then squares(3) {
+///     seen.push(i);
+/// });
+/// assert_eq!(seen, [0, 1, 2, 1, 4, 9]);
+///
+/// let found = 'found: {
+///     cbit::cbit!(for i in up_to(3) then squares(10) break 'found {
+///         if i > 20 {
+///             break 'found Some(i);
+///         }
+///     });
+///     None
+/// };
+/// assert_eq!(found, Some(25));
+/// ```
+///
+/// The body is shared by all iterators so, unlike with a single iterator, the element type can't be
+/// inferred from the first iterator's signature before the body is checked. Bodies which call
+/// methods on the element or access its fields must spell out its type.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn each<'a, T, B>(items: &'a [T], mut f: impl FnMut(&'a T) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for item in items {
+/// #         f(item)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut lengths = 0;
+/// cbit::cbit!(for name: &&str in each(&["ab", "c"]) then each(&["def"]) {
+///     lengths += name.len();
+/// });
+/// assert_eq!(lengths, 6);
+/// ```
+///
+/// Attributes on a chained call only apply to that call, so a `#[cfg(...)]` on it only skips that
+/// iterator. Chained iterators can't be combined with an `unwrap(...)` clause or with the
+/// `#[dyn_callback]` option.
+///
+/// ## Do-While Loops
+///
+/// Prefixing the loop with `do` and following its body with `while <condition>` checks the
+/// condition after every run of the body, including runs ending in a `continue`. The body always
+/// runs for the first element and the condition can use the binding. Once the condition is `false`,
+/// the loop is left as if the body had run a plain `break`, so the loop must be able to evaluate to
+/// `()`.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut seen = Vec::new();
+/// cbit::cbit!(do for i in up_to(10) {
+///     seen.push(i);
+/// } while i < 3);
+/// assert_eq!(seen, [0, 1, 2, 3]);
+///
+/// let mut seen = Vec::new();
+/// cbit::cbit!(do for i in up_to(10) {
+///     seen.push(i);
+/// } while false);
+/// assert_eq!(seen, [0]);
+///
+/// // The condition is still checked when the body continues.
+/// let mut odd = Vec::new();
+/// cbit::cbit!('odd: do for i in up_to(10) {
+///     if i % 2 == 0 {
+///         continue 'odd;
+///     }
+///     odd.push(i);
+/// } while odd.len() < 3);
+/// assert_eq!(odd, [1, 3, 5]);
+/// ```
+///
+/// The condition follows the body so it can't be used with the `=>` form of the body.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut seen = Vec::new();
+/// cbit::cbit!(do for i in up_to(10) => seen.push(i));  // error: a `do` loop requires a `{ ... }` body...
+/// ```
+///
+/// ## Generated Bodies
+///
+/// Code generators can provide the entire body through a macro invocation with
+/// `@body my_macro!(...)`. The macro's expansion is used exactly like a literal body, so `break`,
+/// `continue`, `return`, and `?` in it affect the loop as usual. Unlike with `=> my_macro!(...)`,
+/// the loop doesn't try to tell what the body does from the invocation's tokens and instead assumes
+/// that it may continue the virtual loop or any declared external label. As with any `continue` of
+/// the virtual loop, the body's value must then implement `Default`. The syntax rewritten by `cbit!`
+/// itself, such as `continue with` or `break out`, can't be used in a generated body.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// // e.g. produced by a build script and brought in with `include!`
+/// macro_rules! even_squares_body {
+///     ($i:ident, $out:ident) => {{
+///         if $i % 2 == 1 {
+///             continue;
+///         }
+///         if $i > 6 {
+///             break;
+///         }
+///         $out.push($i * $i);
+///     }};
+/// }
+///
+/// let mut squares = Vec::new();
+/// cbit::cbit!(for i in up_to(10) @body even_squares_body!(i, squares));
+/// assert_eq!(squares, [0, 4, 16, 36]);
+///
+/// // Generated bodies can continue external labels declared in the clause.
+/// macro_rules! next_row_on_zero {
+///     ($cell:ident, $sum:ident, $rows:lifetime) => {{
+///         if $cell == 0 {
+///             continue $rows;
+///         }
+///         $sum += $cell;
+///     }};
+/// }
+///
+/// let mut sum = 0;
+/// 'rows: for row in [[1, 0, 5], [2, 3, 0]] {
+///     cbit::cbit!(for &cell in row.iter().try_for_each() break for 'rows
+///         @body next_row_on_zero!(cell, sum, 'rows));
+/// }
+/// assert_eq!(sum, 6);
+/// ```
+///
+/// ## Redoing Elements
+///
+/// `redo with <value>` stops the current run of the body and immediately runs it again with `value`
+/// bound in place of the element. This doesn't go back to the iterator, which is only resumed once a
+/// run of the body ends without redoing, so it neither advances nor sees the value. Everything else
+/// behaves as if the body were still handling the original element: a `first(...)` flag keeps its
+/// value and the condition of a `do` loop is only checked after the last run.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn each_counted<B>(
+/// #     items: &[u64],
+/// #     calls: &mut usize,
+/// #     mut f: impl FnMut(u64) -> ControlFlow<B>,
+/// # ) -> ControlFlow<B> {
+/// #     for &item in items {
+/// #         *calls += 1;
+/// #         f(item)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut calls = 0;
+/// let mut seen = Vec::new();
+/// cbit::cbit!(for n first(is_first) in each_counted(&[12, 5], &mut calls) {
+///     seen.push((n, is_first));
+///
+///     // Halve every element until it's odd.
+///     if n % 2 == 0 {
+///         redo with n / 2;
+///     }
+/// });
+/// assert_eq!(seen, [(12, true), (6, true), (3, true), (5, false)]);
+/// assert_eq!(calls, 2);
+/// ```
+///
+/// The value must have the element's type since it is bound with the same pattern. Like
+/// `continue with`, `redo with` is only recognized in the body itself and not within the macros it
+/// invokes.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// cbit::cbit!(for i in up_to(10) {
+///     if i == 3 {
+///         redo with "three";  // error: mismatched types
+///     }
+/// });
+/// ```
+///
+/// ## External Labels
+///
+/// Since the body runs inside a closure, it can only reach labels outside of the macro invocation if
+/// they are declared in this clause. Using an undeclared label is a compile error which names the
+/// label and the clause entry to add.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// 'outer: loop {
+///     cbit::cbit!(for i in up_to(10) {
+///         if i == 5 {
+///             break 'outer;  // error: add `break 'outer` after the iterator call
+///         }
+///     });
+/// }
+/// ```
+///
+/// Declaring a label in the clause which does not exist outside of the invocation either is reported
+/// once as an undeclared label pointing at the clause entry.
+///
+/// ```compile_fail,E0426
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// cbit::cbit!(for i in up_to(5) break 'nowhere {  // error: use of undeclared label `'nowhere`
+///     if i == 2 {
+///         break 'nowhere;
+///     }
+/// });
+/// ```
+///
+/// The body runs as part of the enclosing function so `?` propagates errors out of it just like in a
+/// regular loop. This composes with breaking out to an external label: `break 'label expr?` parses
+/// as `break 'label (expr?)` so an error returns from the function before the label is reached while
+/// a successful value is what the labeled block evaluates to.
+///
+/// ```
+/// # use std::{num::ParseIntError, ops::ControlFlow};
+/// # fn each<'a, T, B>(items: &'a [T], mut f: impl FnMut(&'a T) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for item in items {
+/// #         f(item)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// fn first_tagged(items: &[&str]) -> Result<Option<u32>, ParseIntError> {
+///     let found = 'found: {
+///         cbit::cbit!(for item in each(items) break 'found {
+///             if let Some(digits) = item.strip_prefix('#') {
+///                 break 'found Some(digits.parse::<u32>()?);
+///             }
+///         });
+///         None
+///     };
+///     Ok(found)
+/// }
+///
+/// assert_eq!(first_tagged(&["a", "#12", "#x"]), Ok(Some(12)));
+/// assert!(first_tagged(&["a", "#x", "#1"]).is_err());
+/// assert_eq!(first_tagged(&["a", "b"]), Ok(None));
+/// ```
+///
+/// Rust has no labels for `match` arms or `if` branches but any position producing a value can be
+/// targeted by wrapping it in a labeled block.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// fn first_multiple(of: Option<u64>) -> &'static str {
+///     match of {
+///         Some(of) => 'arm: {
+///             cbit::cbit!(for i in up_to(10) break 'arm {
+///                 if i > 0 && i % of == 0 {
+///                     break 'arm "found";
+///                 }
+///             });
+///             "not found"
+///         }
+///         None => "no divisor",
+///     }
+/// }
+///
+/// assert_eq!(first_multiple(Some(3)), "found");
+/// assert_eq!(first_multiple(Some(20)), "not found");
+/// assert_eq!(first_multiple(None), "no divisor");
+/// ```
+///
+/// Labels on `while`, `while let`, and `for` loops can be both broken out of and continued but
+/// Rust forbids breaking out of them with a value so they must be declared with the matching keyword.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut stack = vec![3, 10, 2];
+/// let mut seen = Vec::new();
+///
+/// 'work: while let Some(n) = stack.pop() {
+///     cbit::cbit!(for i in up_to(n) break while 'work {
+///         if i == 5 {
+///             break 'work;
+///         }
+///         if i == 1 && n < 5 {
+///             continue 'work;
+///         }
+///         seen.push(i);
+///     });
+/// }
+///
+/// assert_eq!(seen, [0, 0, 1, 2, 3, 4]);
+/// assert_eq!(stack, [3]);
+/// ```
+///
+/// The machinery to continue a label is only generated if the body contains a `continue` of it, or
+/// hands the label or a `continue` to a macro invocation, so declaring a label as a `loop` which is
+/// only ever broken out of costs nothing and produces no warnings.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// #[deny(warnings)]
+/// fn first_square_above(n: u64) -> Option<u64> {
+///     'search: loop {
+///         cbit::cbit!(for i in up_to(n) break loop 'search {
+///             if i * i > n {
+///                 break 'search Some(i);
+///             }
+///         });
+///         break None;
+///     }
+/// }
+///
+/// assert_eq!(first_square_above(10), Some(4));
+/// assert_eq!(first_square_above(0), None);
+/// ```
+///
+/// Rust's `continue` cannot carry a value but, since outer loops are often driven by some state, the
+/// body can `continue 'label with <value>` to a label declared as `loop 'label with <target>`. The
+/// value is assigned to `target`—which is evaluated outside of the `cbit!` loop—right before the
+/// outer loop is continued. A plain `continue 'label` leaves `target` untouched.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut limit = 10;
+/// let mut rounds = Vec::new();
+///
+/// 'rounds: loop {
+///     rounds.push(limit);
+///
+///     cbit::cbit!(for i in up_to(limit) break loop 'rounds with limit {
+///         if i * i >= limit {
+///             continue 'rounds with i;
+///         }
+///     });
+///     break;
+/// }
+///
+/// assert_eq!(rounds, [10, 4, 2]);
+/// ```
+///
+/// Only the `continue 'label with <value>` form can carry a value. Continuing with a value in any
+/// other way—including to a label declared without a target—is a compile error explaining how to
+/// declare the label.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// 'rounds: loop {
+///     cbit::cbit!(for i in up_to(10) break loop 'rounds {
+///         continue 'rounds with i;  // error: declare it as `loop 'rounds with <target>`
+///     });
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// cbit::cbit!(for i in up_to(10) {
+///     continue i;  // error: `continue` cannot carry a value
+/// });
+/// ```
+///
+/// The macro can't see what an external label is attached to, so declaring a labeled block as a
+/// `loop`, `while`, or `for` label is only caught once the body continues it. The resulting error
+/// points at the clause entry making the claim.
+///
+/// ```compile_fail,E0696
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// 'block: {
+///     cbit::cbit!(for i in up_to(10) break loop 'block {
+///         //                         ^^^^^^^^^^^ error: labeled blocks cannot be `continue`'d
+///         if i == 3 {
+///             continue 'block;
+///         }
+///     });
+/// }
+/// ```
+///
+/// A common pattern is to wrap the `cbit!` loop in a labeled block so that the body can produce a
+/// value for the block other than the one the loop completes with. Declaring the label as
+/// `out 'label` lets the body `break out <value>` (or just `break out`) to it without repeating the
+/// label's name. Directly within the body, `out` is treated as a keyword so `break out` can't break
+/// out of the virtual loop with the value of a variable named `out`. Within regular `loop`, `while`,
+/// and `for` loops, closures, and items, `break out` keeps its regular meaning so those must name the
+/// label instead.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let first_square_above = |n: u64| 'search: {
+///     cbit::cbit!(for i in up_to(n) break out 'search {
+///         if i * i > n {
+///             break out Some(i);
+///         }
+///     });
+///     None
+/// };
+///
+/// assert_eq!(first_square_above(10), Some(4));
+/// assert_eq!(first_square_above(0), None);
+/// ```
+///
+/// ## Bindings
+///
+/// In addition to patterns, the binding can be a [destructuring assignment](https://doc.rust-lang.org/reference/expressions/operator-expr.html#destructuring-assignments)
+/// target such as `(self.x, self.y)`, in which case the existing places are assigned to at the start
+/// of every iteration. If the binding parses as a pattern, it is treated as one, so `(a, b)` still
+/// introduces fresh bindings.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// fn pairs<B>(mut f: impl FnMut((i32, i32)) -> ControlFlow<B>) -> ControlFlow<B> {
+///     f((1, 2))?;
+///     f((3, 4))?;
+///     ControlFlow::Continue(())
+/// }
+///
+/// struct Cursor {
+///     x: i32,
+///     y: i32,
+///     visited: Vec<(i32, i32)>,
+/// }
+///
+/// impl Cursor {
+///     fn replay(&mut self) {
+///         cbit::cbit!(for (self.x, self.y) in pairs() {
+///             self.visited.push((self.x, self.y));
+///         });
+///     }
+/// }
+///
+/// let mut cursor = Cursor { x: 0, y: 0, visited: Vec::new() };
+/// cursor.replay();
+/// assert_eq!((cursor.x, cursor.y), (3, 4));
+/// assert_eq!(cursor.visited, [(1, 2), (3, 4)]);
+/// ```
+///
+/// Like closure parameters, bindings can be annotated with the type of the elements they receive,
+/// which is useful when the iterator function is generic over its elements and the body alone cannot
+/// drive inference. The type can be written after the binding or, equivalently, as a leading
+/// turbofish.
+///
+/// ```
+/// # use std::{ops::ControlFlow, str::FromStr};
+/// fn parse_each<T: FromStr, B>(text: &str, mut f: impl FnMut(T) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for word in text.split_whitespace() {
+///         if let Ok(v) = word.parse() {
+///             f(v)?;
+///         }
 ///     }
 ///     ControlFlow::Continue(())
 /// }
+///
+/// let mut squares = 0;
+/// cbit::cbit!(for v: u32 in parse_each("1 2 three 4") {
+///     squares += v.pow(2);
+/// });
+/// assert_eq!(squares, 21);
+///
+/// let mut squares = 0;
+/// cbit::cbit!(::<u32> for v in parse_each("1 2 three 4") {
+///     squares += v.pow(2);
+/// });
+/// assert_eq!(squares, 21);
 /// ```
 ///
-/// The `Continue` parameter of the `ControlFlow` objects, meanwhile, is a lot more flexible. The
-/// `Continue` parameter on the return type of the inner closure designates the type users are expected
-/// to give back to the calling iterator function. Since users can run `continue` in the body, this
-/// type must implement [`Default`].
+/// Without the annotation, the element type is unknown at the point of the method call.
 ///
-/// The `Continue` parameter on the return type of the iterator function, meanwhile, can be used to
-/// return values from the `cbit!` macro expression. If users `break` out of loops with a non-unit
-/// output `Continue` type, they must provide this value themself.
+/// ```compile_fail
+/// # use std::{ops::ControlFlow, str::FromStr};
+/// # fn parse_each<T: FromStr, B>(text: &str, mut f: impl FnMut(T) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for word in text.split_whitespace() {
+/// #         if let Ok(v) = word.parse() {
+/// #             f(v)?;
+/// #         }
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut squares = 0u32;
+/// cbit::cbit!(for v in parse_each("1 2 three 4") {
+///     squares += v.pow(2);  // error: type annotations needed
+/// });
+/// ```
+///
+/// When the iterator function bounds its callback by a trait other than `FnMut`, the lifetimes of
+/// borrowed elements cannot be inferred from the call and must be spelled out in the element type.
+/// Those lifetimes can be named in a `for<...>` binder after the `for` keyword. Closures can't
+/// declare lifetime parameters on stable Rust so each lifetime of the binder is elided on the body
+/// closure's argument, which makes the body generic over it just the same. For that reason, each
+/// lifetime can only be used once in the element type and cannot be named in the body.
 ///
 /// ```
 /// use std::ops::ControlFlow;
 ///
-/// fn demo(list: &[i32]) -> i32 {
-///     cbit::cbit!(for (accum, value) in reduce(0, list) {
-///         if *value > 100 {
-///             break -1;
-///         }
-///         accum + value
-///     })
+/// trait Visitor<B> {
+///     fn visit(&mut self, entry: (&str, &[u32])) -> ControlFlow<B>;
 /// }
 ///
-/// fn reduce<T, I: IntoIterator, B>(
-///     initial: T,
-///     values: I,
-///     mut f: impl FnMut((T, I::Item)) -> ControlFlow<B, T>,
-/// ) -> ControlFlow<B, T> {
-///     let mut accum = initial;
-///     for value in values {
-///         accum = f((accum, value))?;
+/// impl<B, F: FnMut((&str, &[u32])) -> ControlFlow<B>> Visitor<B> for F {
+///     fn visit(&mut self, entry: (&str, &[u32])) -> ControlFlow<B> {
+///         self(entry)
 ///     }
-///     ControlFlow::Continue(accum)
 /// }
 ///
-/// assert_eq!(demo(&[1, 2, 3]), 6);
-/// assert_eq!(demo(&[1, 2, 3, 4, 101, 8]), -1);
+/// fn entries<B>(mut visitor: impl Visitor<B>) -> ControlFlow<B> {
+///     for name in ["a", "bc"] {
+///         let scores = name.bytes().map(u32::from).collect::<Vec<_>>();
+///         visitor.visit((&name.repeat(2), &scores))?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// let mut total = 0;
+/// cbit::cbit!(for<'n, 's> (name, scores): (&'n str, &'s [u32]) in entries() {
+///     total += name.len() as u32 + scores.iter().sum::<u32>();
+/// });
+/// assert_eq!(total, 2 + 97 + 4 + 98 + 99);
 /// ```
-#[proc_macro]
-pub fn cbit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn pairs<B>(mut f: impl FnMut((&str, &str)) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     f(("a", "b"))
+/// # }
+/// cbit::cbit!(for<'a> (x, y): (&'a str, &'a str) in pairs() {  // error: `'a` is used more than once
+///     println!("{x}{y}");
+/// });
+/// ```
+///
+/// A `first(<name>)` clause after the binding introduces a `bool` that is only `true` during the
+/// first run of the body, which is handy when joining elements with a separator. Every run of the
+/// body counts, including one which `continue`s early.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut list = String::new();
+/// cbit::cbit!(for i first(is_first) in up_to(4) {
+///     if !is_first {
+///         list.push_str(", ");
+///     }
+///     list.push_str(&i.to_string());
+/// });
+/// assert_eq!(list, "0, 1, 2, 3");
+///
+/// let mut seen = Vec::new();
+/// cbit::cbit!(for i: u64 first(is_first) in up_to(3) {
+///     if i == 0 {
+///         continue;  // This still counts as the first run.
+///     }
+///     seen.push((i, is_first));
+/// });
+/// assert_eq!(seen, [(1, false), (2, false)]);
+/// ```
+///
+/// Loops which only care about the side effects of the iterator can leave the binding out
+/// altogether, which is shorthand for `for _ in`.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut runs = 0;
+/// cbit::cbit!(for in up_to(4) {
+///     runs += 1;
+/// });
+/// assert_eq!(runs, 4);
+/// ```
+///
+/// `@`-bindings work like they do in closure parameters, binding the whole element while also
+/// destructuring it.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn pairs<B>(mut f: impl FnMut((i32, i32)) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     f((1, 2))?;
+/// #     f((3, 4))?;
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut seen = Vec::new();
+/// cbit::cbit!(for pair @ (a, b) in pairs() {
+///     seen.push((pair, a * b));
+/// });
+/// assert_eq!(seen, [((1, 2), 2), ((3, 4), 12)]);
+/// ```
+///
+/// As with any other binding, the subpattern must be irrefutable.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// cbit::cbit!(for n @ 1..=5 in up_to(10) {  // error: refutable pattern in closure argument
+///     println!("{n}");
+/// });
+/// ```
+///
+/// As in a regular loop, each element only lives for the run of the body which received it. Values
+/// the loop is broken out of with can hold on to whatever the element borrows from but not to the
+/// element itself. Owned parts of an element can still be moved out, and borrowed ones cloned.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// fn each<'a, T, B>(items: &'a [T], mut f: impl FnMut(&'a T) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for item in items {
+///         f(item)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// fn numbered<B>(mut f: impl FnMut(String) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..5 {
+///         f(format!("item {i}"))?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// // The elements are borrows of `words`, which outlives the loop.
+/// let words = ["apple".to_string(), "banana".to_string()];
+/// let found = 'found: {
+///     cbit::cbit!(for word in each(&words) break 'found {
+///         if word.starts_with('b') {
+///             break 'found Some(&word[1..]);
+///         }
+///     });
+///     None
+/// };
+/// assert_eq!(found, Some("anana"));
+///
+/// // Owned elements can be moved out of or have values derived from them.
+/// let found = 'found: {
+///     cbit::cbit!(for item in numbered() break 'found {
+///         if item.ends_with('3') {
+///             break 'found Some(item);
+///         }
+///     });
+///     None
+/// };
+/// assert_eq!(found.as_deref(), Some("item 3"));
+/// ```
+///
+/// Breaking out with a borrow of an owned element is rejected. Since the body runs in an `async`
+/// block, `rustc` reports this as the block borrowing the element beyond its lifetime (and suggests
+/// a `move` which won't help) rather than as the element not living long enough.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn numbered<B>(mut f: impl FnMut(String) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..5 {
+/// #         f(format!("item {i}"))?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let found: Option<&str> = 'found: {
+///     cbit::cbit!(for item in numbered() break 'found {
+///         if item.ends_with('3') {
+///             break 'found Some(&item[5..]);  // error: async block may outlive ... borrows `item`
+///         }
+///     });
+///     None
+/// };
+/// ```
+///
+/// Patterns cannot fill in defaults for missing parts of an element. Elements containing `Option`s
+/// are bound like any other value and defaulted in the body instead.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// fn sizes<B>(mut f: impl FnMut((&'static str, Option<u32>)) -> ControlFlow<B>) -> ControlFlow<B> {
+///     f(("a", Some(2)))?;
+///     f(("b", None))?;
+///     ControlFlow::Continue(())
+/// }
+///
+/// let mut total = 0;
+/// cbit::cbit!(for (_, size) in sizes() {
+///     total += size.unwrap_or(10);
+/// });
+/// assert_eq!(total, 12);
+/// ```
+///
+/// Default-like syntax such as `(name, size = 10)` or `(name, size?)` is rejected with an error
+/// saying as much.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn sizes<B>(mut f: impl FnMut((&'static str, Option<u32>)) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     f(("a", Some(2)))?;
+/// #     f(("b", None))?;
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut total = 0;
+/// cbit::cbit!(for (_, size = 10) in sizes() {  // error: bindings cannot specify default values
+///     total += size;
+/// });
+/// ```
+///
+/// ## Options
+///
+/// - `#[trace]`: logs the control-flow path through which the loop was exited (e.g. `EarlyReturn`,
+///   `EarlyBreak`, or `EarlyBreakTo<Label>`) to stderr along with the location of the macro
+///   invocation. This is a debugging aid for loops with many external labels and only has an effect
+///   when the `trace` feature is enabled. Otherwise, it expands to nothing.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let found = 'search: {
+///     cbit::cbit!(#[trace] for i in up_to(10) break 'search {
+///         if i == 3 {
+///             break 'search true;  // Logs `EarlyBreakToSearch` with the `trace` feature.
+///         }
+///     });
+///     false
+/// };
+/// assert!(found);
+/// ```
+///
+/// - `#[warn_unused_labels]`: reports external labels declared in the `break` clause which the body
+///   never uses through rustc's `unused_labels` lint, pointing at their declaration. This helps keep
+///   the clauses of large loops from accumulating stale labels. A label counts as used if the body
+///   mentions it anywhere, including in the arguments of a macro, or uses it through `break out`
+///   or `#[numbered_breaks]`. Bodies which can't be inspected, such as those given with `@body`,
+///   are never reported.
+///
+/// ```compile_fail
+/// #![deny(unused_labels)]
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// 'rows: for row in 0..3 {
+///     'cells: loop {
+///         cbit::cbit!(#[warn_unused_labels] for i in up_to(row) break for 'rows, loop 'cells {
+///             //                                                                      ^^^^^^
+///             // error: unused label
+///             if i == 1 {
+///                 continue 'rows;
+///             }
+///         });
+///         break;
+///     }
+/// }
+/// ```
+///
+/// ```
+/// #![deny(unused_labels)]
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let mut found = None;
+/// 'rows: for row in 0..3 {
+///     'cells: loop {
+///         cbit::cbit!(#[warn_unused_labels] for i in up_to(row) break for 'rows, loop 'cells {
+///             if i == 1 {
+///                 continue 'rows;
+///             }
+///             if i == 2 {
+///                 found = Some(row);
+///                 break 'cells;
+///             }
+///         });
+///         break;
+///     }
+/// }
+/// assert_eq!(found, None);
+/// ```
+///
+/// - `#[hoist_args]`: evaluates the arguments of the iterator call into locals before the call
+///   itself. Ordinarily, temporaries created while evaluating the arguments live until the end of the
+///   statement containing the call and are therefore held for the entire loop. Hoisting drops them
+///   before the first iteration, which resolves conflicts such as a `RefCell` guard being held while
+///   the body tries to borrow the cell again.
+///
+///   The receiver of a method call is not hoisted since it is borrowed by the call anyway and
+///   hoisting it would change how it is auto-referenced. Note that this means the receiver is
+///   evaluated after the arguments. Closure literals passed as arguments also lose the signature
+///   the call would otherwise provide for inferring their parameter types.
+///
+/// ```should_panic
+/// # use std::{cell::RefCell, ops::ControlFlow};
+/// # fn up_to<B>(n: usize, mut f: impl FnMut(usize) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let values = RefCell::new(vec![1, 2, 3]);
+///
+/// // The `Ref` guard created by `borrow()` outlives the body's `borrow_mut()`.
+/// cbit::cbit!(for i in up_to(values.borrow().len()) {
+///     values.borrow_mut()[i] *= 2;  // panics: already borrowed
+/// });
+/// ```
+///
+/// ```
+/// # use std::{cell::RefCell, ops::ControlFlow};
+/// # fn up_to<B>(n: usize, mut f: impl FnMut(usize) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let values = RefCell::new(vec![1, 2, 3]);
+///
+/// cbit::cbit!(#[hoist_args] for i in up_to(values.borrow().len()) {
+///     values.borrow_mut()[i] *= 2;
+/// });
+/// assert_eq!(*values.borrow(), [2, 4, 6]);
+/// ```
+///
+/// - `#[dyn_callback]`: passes the closure to the iterator as a `&mut` reference and hides the
+///   loop's `Break` payload from the iterator, which only ever sees `Break(())`. This allows driving
+///   object-safe iterators taking a `&mut dyn FnMut(...) -> ControlFlow<()>`, which cannot be
+///   generic over the `Break` type. Such iterators must only break when their callback does.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// trait Walkable {
+///     fn walk(&self, f: &mut dyn FnMut(u32) -> ControlFlow<()>) -> ControlFlow<()>;
+/// }
+///
+/// struct Range(u32);
+///
+/// impl Walkable for Range {
+///     fn walk(&self, f: &mut dyn FnMut(u32) -> ControlFlow<()>) -> ControlFlow<()> {
+///         for i in 0..self.0 {
+///             f(i)?;
+///         }
+///         ControlFlow::Continue(())
+///     }
+/// }
+///
+/// let walkable: &dyn Walkable = &Range(10);
+/// let found = 'search: {
+///     cbit::cbit!(#[dyn_callback] for i in walkable.walk() break 'search {
+///         if i * i > 20 {
+///             break 'search Some(i);
+///         }
+///     });
+///     None
+/// };
+/// assert_eq!(found, Some(5));
+/// ```
+///
+/// Function pointers can't be generic either so iterators selected at runtime through a `fn(...)`
+/// pointer, whether it is stored in a local or in a field, are driven the same way. A local bound to
+/// the function item itself (`let drive = up_to;`) stays generic and needs no option.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// type Driver = fn(u32, &mut dyn FnMut(u32) -> ControlFlow<()>) -> ControlFlow<()>;
+///
+/// fn up_to(n: u32, f: &mut dyn FnMut(u32) -> ControlFlow<()>) -> ControlFlow<()> {
+///     for i in 0..n {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// fn down_from(n: u32, f: &mut dyn FnMut(u32) -> ControlFlow<()>) -> ControlFlow<()> {
+///     for i in (0..n).rev() {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// fn up_to_generic<B>(n: u32, mut f: impl FnMut(u32) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// struct Config {
+///     order: Driver,
+/// }
+///
+/// let drive: Driver = if cfg!(test) { up_to } else { down_from };
+/// let mut seen = Vec::new();
+/// cbit::cbit!(#[dyn_callback] for i in drive(3) {
+///     seen.push(i);
+/// });
+/// assert_eq!(seen.len(), 3);
+///
+/// let config = Config { order: down_from };
+/// let mut seen = Vec::new();
+/// cbit::cbit!(#[dyn_callback] for i in (config.order)(5) {
+///     if i == 1 {
+///         break;
+///     }
+///     seen.push(i);
+/// });
+/// assert_eq!(seen, [4, 3, 2]);
+///
+/// let drive = up_to_generic;
+/// let mut sum = 0;
+/// cbit::cbit!(for i in drive(4) {
+///     sum += i;
+/// });
+/// assert_eq!(sum, 6);
+/// ```
+///
+/// - `#[infinite]`: declares that the iterator never completes on its own. Ordinarily, the value of
+///   a `cbit!` loop must have the same type as the `Continue` value with which the iterator
+///   completes so a body which always `break`s or `return`s out of an infinite stream still needs
+///   to handle a completion which can never happen. With this option, the iterator must instead
+///   complete with an uninhabited type (e.g. `ControlFlow<B, Infallible>`) and the type of the loop
+///   is determined solely by its `break` expressions.
+///
+/// ```
+/// use std::{convert::Infallible, ops::ControlFlow};
+///
+/// fn naturals<B>(mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B, Infallible> {
+///     let mut i = 0;
+///     loop {
+///         f(i)?;
+///         i += 1;
+///     }
+/// }
+///
+/// fn first_square_above(n: u64) -> u64 {
+///     cbit::cbit!(#[infinite] for i in naturals() {
+///         if i * i > n {
+///             break i;
+///         }
+///     })
+/// }
+///
+/// assert_eq!(first_square_above(10), 4);
+/// ```
+///
+/// Iterators which can complete are rejected.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// cbit::cbit!(#[infinite] for i in up_to(10) {  // error: type `()` is non-empty
+///     if i == 3 {
+///         break;
+///     }
+/// });
+/// ```
+///
+/// - `#[numbered_breaks]` (experimental): allows the body to `break <depth>` or `continue <depth>`
+///   where `depth` counts nested `cbit!` loops outwards, starting at 1 for the innermost one. The
+///   option applies to the loop it is given to and every `cbit!` loop nested within its body. Only
+///   `cbit!` loops are counted, not regular loops or labeled blocks. Within a regular `loop`,
+///   `while`, or `for` loop, `break` and `continue` keep referring to that loop so `break 1` there
+///   breaks it with the value `1`, although `cbit!` loops nested within it still count depths.
+///   Closures, `async` and `const` blocks, and items are left alone entirely. Elsewhere, an integer
+///   literal after `break` is always taken to be a depth rather than a value. Labels are usually
+///   clearer than depths so this is mostly useful for porting code from languages with numbered
+///   breaks.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// use cbit::cbit;
+///
+/// let mut pairs = Vec::new();
+/// cbit!(#[numbered_breaks] for i in up_to(4) {
+///     cbit!(for j in up_to(4) {
+///         if j > i {
+///             continue 2;
+///         }
+///         if i == 2 {
+///             break 2;
+///         }
+///         pairs.push((i, j));
+///     });
+/// });
+/// assert_eq!(pairs, [(0, 0), (1, 0), (1, 1)]);
+///
+/// let mut triples = Vec::new();
+/// cbit!(#[numbered_breaks] for i in up_to(3) {
+///     cbit!(for j in up_to(3) {
+///         cbit!(for k in up_to(3) {
+///             if k == 1 {
+///                 continue 2;
+///             }
+///             if j == 1 {
+///                 continue 3;
+///             }
+///             if i == 2 {
+///                 break 3;
+///             }
+///             triples.push((i, j, k));
+///         });
+///     });
+/// });
+/// assert_eq!(triples, [(0, 0, 0), (1, 0, 0)]);
+///
+/// let mut values = Vec::new();
+/// cbit!(#[numbered_breaks] for i in up_to(4) {
+///     // This breaks the regular loop with the value `1` rather than the `cbit!` loop.
+///     let one = loop {
+///         break 1;
+///     };
+///     let mut j = 0;
+///     while j < i {
+///         j += 1;
+///         cbit!(for k in up_to(4) {
+///             if k == j {
+///                 continue 2;
+///             }
+///             if i == 3 {
+///                 break 2;
+///             }
+///             values.push((i + one, k));
+///         });
+///     }
+/// });
+/// assert_eq!(values, [(2, 0), (3, 0)]);
+/// ```
+///
+/// - `#[on_await_misuse(<handler>)]`: replaces the panic raised when an `.await` in the body
+///   suspends it. The handler is called with no arguments and must diverge, so it is typically a
+///   `fn() -> !` path or a closure which panics, aborts, or exits with a message of its own. It only
+///   runs on genuine misuse: futures which complete immediately never reach it.
+///
+/// ```should_panic
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// fn misused() -> ! {
+///     panic!("`up_to` cannot be awaited within");
+/// }
+///
+/// cbit::cbit!(#[on_await_misuse(misused)] for i in up_to(3) {
+///     if i == 1 {
+///         std::future::pending::<()>().await;
+///     }
+/// });
+/// ```
+///
+/// - `#[on_break(<hook>)]`: passes every value the loop is broken out of with through `hook` before
+///   the loop evaluates to it. The hook can be used to observe the value (e.g. for logging) or to map
+///   it to another value, in which case its output must have the type the loop completes with. It is
+///   only called for `break`s of this loop and not when the iterator completes or when the body
+///   `return`s, `continue`s, or breaks out to an external label. Without the option, the value is
+///   yielded as-is. In [try mode](#try-mode), the hook receives the value before `?` is applied.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn search<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B, Option<u64>> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(None)
+/// # }
+/// let mut log = Vec::new();
+/// let mut first_above = |min| {
+///     cbit::cbit!(#[on_break(|v| { log.push(v); v })] for i in search(10) {
+///         if i * i > min {
+///             break Some(i);
+///         }
+///     })
+/// };
+/// assert_eq!(first_above(10), Some(4));
+/// assert_eq!(first_above(40), Some(7));
+/// assert_eq!(first_above(100), None);
+/// assert_eq!(log, [Some(4), Some(7)]);
+///
+/// let doubled = cbit::cbit!(#[on_break(|v: Option<u64>| v.map(|i| i * 2))] for i in search(10) {
+///     if i == 3 {
+///         break Some(i);
+///     }
+/// });
+/// assert_eq!(doubled, Some(6));
+/// ```
+///
+/// - `#[control_flow(<path>)]`: drives an iterator function which uses a look-alike of
+///   [`ControlFlow`](::std::ops::ControlFlow) rather than the real thing, e.g. because its crate
+///   predates it. The path must name an enum generic over its `Break` and `Continue` types, in that
+///   order, with `Break(B)` and `Continue(C)` variants. Everything else about the
+///   [iteration protocol](#iteration-protocol) stays the same.
+///
+/// ```
+/// mod legacy {
+///     pub enum Step<B, C = ()> {
+///         Continue(C),
+///         Break(B),
+///     }
+///
+///     pub fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> Step<B>) -> Step<B> {
+///         for i in 0..n {
+///             if let Step::Break(value) = f(i) {
+///                 return Step::Break(value);
+///             }
+///         }
+///         Step::Continue(())
+///     }
+/// }
+///
+/// let mut seen = Vec::new();
+/// cbit::cbit!(#[control_flow(legacy::Step)] for i in legacy::up_to(10) {
+///     if i == 1 {
+///         continue;
+///     }
+///     if i == 4 {
+///         break;
+///     }
+///     seen.push(i);
+/// });
+/// assert_eq!(seen, [0, 2, 3]);
+/// ```
+///
+/// ## Try Mode
+///
+/// There are two places where errors can be short-circuited out of a loop:
+///
+/// - A `?` in the body always propagates the error out of the enclosing function, just like in a
+///   regular loop.
+/// - With the `try` prefix, `?` is additionally applied to every value the virtual loop is broken
+///   out of with. A `break Err(e)` then returns `Err(e)` (after the usual `From` conversion) from the
+///   enclosing function while a `break Ok(v)` makes the loop evaluate to `v`. Values broken out to
+///   external labels and values with which the iterator completes are left untouched.
+///
+/// Since the broken-out values are unwrapped, the type with which the iterator completes is no
+/// longer tied to them and must instead match their unwrapped type.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// fn sum_until_stop(items: &[&str]) -> Result<u64, String> {
+///     let mut sum = 0;
+///     cbit::cbit!(try for i in up_to(items.len() as u64) {
+///         match items[i as usize] {
+///             "stop" => break Ok(()),
+///             "abort" => break Err(format!("aborted at {i}")),
+///             // Propagated out of the function in any mode.
+///             item => sum += item.parse::<u64>().map_err(|err| err.to_string())?,
+///         }
+///     });
+///     Ok(sum)
+/// }
+///
+/// assert_eq!(sum_until_stop(&["1", "2"]), Ok(3));
+/// assert_eq!(sum_until_stop(&["1", "stop", "abort"]), Ok(1));
+/// assert_eq!(sum_until_stop(&["1", "abort", "stop"]), Err("aborted at 1".to_string()));
+/// assert!(sum_until_stop(&["x"]).is_err());
+/// ```
+///
+/// ## Raw Results
+///
+/// Ordinarily, the loop ends in a `match` which performs whatever control flow the body asked for.
+/// Prefixing the loop with `raw <name> =>` skips that `match` and instead binds the result of the
+/// iterator call to `name` for a handler expression following the body and a `;`. The loop then
+/// evaluates to the handler. This gives wrappers full control over each way the loop can end, for
+/// example to turn early `return`s into values.
+///
+/// The result is a `ControlFlow<Outcome<...>, C>` where `C` is the value the iterator completed
+/// with. `Outcome` is an enum generated by the macro and is in scope within the handler. It has the
+/// following variants:
+///
+/// - `EarlyReturn(value)`: the body `return`ed `value`.
+/// - `EarlyBreak(value)`: the body broke out of the loop with `value`.
+/// - `EarlyBreakTo<Label>(value)`: the body broke out to the external label `'label` with `value`.
+///   `Label` is the label's name in `UpperCamelCase` (e.g. `'outer_rows` becomes `OuterRows`).
+/// - `EarlyContinueTo<Label>`: the body continued the external `loop`, `while`, or `for` label.
+/// - `EarlyContinueWithTo<Label>(value)`: the body continued the external label with `value` using
+///   `continue '<label> with`.
+///
+/// The handler is responsible for every one of them. A `raw` loop requires a `{ ... }` body and
+/// cannot be combined with the `try` prefix, `#[on_break(...)]`, or `#[numbered_breaks]`, all of
+/// which act on the `match` the handler replaces.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn search<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B, Option<u64>> {
+///     for i in 0..n {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(None)
+/// }
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Exit {
+///     Completed(Option<u64>),
+///     Broke(Option<u64>),
+///     Returned(&'static str),
+///     BrokeOuter(u32),
+///     ContinuedOuter,
+/// }
+///
+/// fn run(mode: u32) -> Exit {
+///     let mut continued = false;
+///     'outer: loop {
+///         if continued {
+///             return Exit::ContinuedOuter;
+///         }
+///         continued = true;
+///
+///         return cbit::cbit!(raw outcome => for i in search(5) break loop 'outer {
+///             if i == 2 {
+///                 match mode {
+///                     0 => break Some(i),
+///                     1 => return "returned",
+///                     2 => break 'outer 7,
+///                     3 => continue 'outer,
+///                     _ => {}
+///                 }
+///             }
+///         }; match outcome {
+///             ControlFlow::Continue(value) => Exit::Completed(value),
+///             ControlFlow::Break(Outcome::EarlyBreak(value)) => Exit::Broke(value),
+///             ControlFlow::Break(Outcome::EarlyReturn(value)) => Exit::Returned(value),
+///             ControlFlow::Break(Outcome::EarlyBreakToOuter(value)) => Exit::BrokeOuter(value),
+///             ControlFlow::Break(Outcome::EarlyContinueToOuter) => continue 'outer,
+///         });
+///     }
+/// }
+///
+/// assert_eq!(run(0), Exit::Broke(Some(2)));
+/// assert_eq!(run(1), Exit::Returned("returned"));
+/// assert_eq!(run(2), Exit::BrokeOuter(7));
+/// assert_eq!(run(3), Exit::ContinuedOuter);
+/// assert_eq!(run(4), Exit::Completed(None));
+/// ```
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// let _ = cbit::cbit!(raw outcome => try for i in up_to(5) {  // error: ...cannot be used in a `raw` loop
+///     if i == 2 {
+///         break Ok::<(), ()>(());
+///     }
+/// }; outcome);
+/// ```
+///
+/// ## Iteration Protocol
+///
+/// The called function or method can take on any non-zero number of arguments but must accept a
+/// single-argument function closure as its last argument. The closure must be able to return a
+/// [`ControlFlow`](::std::ops::ControlFlow) object with a generic `Break` type and the function must
+/// return a `ControlFlow` object with the same `Break` type.
+///
+/// ```
+/// use std::{iter::IntoIterator, ops::ControlFlow};
+///
+/// // A simple example...
+/// fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// // A slightly more involved example...
+/// fn enumerate<I: IntoIterator, B>(
+///     values: I,
+///     index_offset: usize,
+///     mut f: impl FnMut((usize, I::Item),
+/// ) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for (i, v) in values.into_iter().enumerate() {
+///         f((i + index_offset, v))?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+/// ```
+///
+/// [`Iterator::try_for_each`](::core::iter::Iterator::try_for_each) follows this protocol so any
+/// standard iterator can be driven by calling it without arguments. Driving the iterator through
+/// [`by_ref`](::core::iter::Iterator::by_ref) leaves whatever the loop didn't consume in it.
+///
+/// ```
+/// let mut sum = 0;
+/// cbit::cbit!(for i in (0..10).try_for_each() {
+///     if i == 5 {
+///         break;
+///     }
+///     sum += i;
+/// });
+/// assert_eq!(sum, 10);
+///
+/// let words = vec!["a", "bb", "", "ccc"];
+/// let mut iter = words.into_iter();
+/// let mut lengths = Vec::new();
+/// cbit::cbit!(for word in iter.by_ref().try_for_each() {
+///     if word.is_empty() {
+///         break;
+///     }
+///     lengths.push(word.len());
+/// });
+/// assert_eq!(lengths, [1, 2]);
+/// assert_eq!(iter.next(), Some("ccc"));
+/// ```
+///
+/// The closure is appended to the arguments given in the call so the call must leave it out. If it
+/// doesn't, the arity error is reported on the called function.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+/// #     for i in 0..n {
+/// #         f(i)?;
+/// #     }
+/// #     ControlFlow::Continue(())
+/// # }
+/// cbit::cbit!(for i in up_to(10, |_| ControlFlow::Continue(())) {
+///     //               ^^^^^ error: this function takes 2 arguments but 3 arguments were supplied
+/// });
+/// ```
+///
+/// The `Continue` parameter of the `ControlFlow` objects, meanwhile, is a lot more flexible. The
+/// `Continue` parameter on the return type of the inner closure designates the type users are expected
+/// to give back to the calling iterator function. Since users can run `continue` in the body, this
+/// type must implement [`Default`]. Alternatively, the body can hand a specific value back with
+/// `continue with <value>` (or `continue '<loop-label> with <value>`), which always continues the
+/// virtual loop. If the body never uses a plain `continue` on the virtual loop, the type doesn't
+/// need to implement `Default`.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// struct Total(u32);  // Not `Default`
+///
+/// fn accumulate<B>(n: u32, mut f: impl FnMut((u32, u32)) -> ControlFlow<B, Total>) -> ControlFlow<B, u32> {
+///     let mut total = 0;
+///     for i in 0..n {
+///         total = f((total, i))?.0;
+///     }
+///     ControlFlow::Continue(total)
+/// }
+///
+/// let total = cbit::cbit!(for (total, i) in accumulate(5) {
+///     if i % 2 == 0 {
+///         continue with Total(total);
+///     }
+///     Total(total + i)
+/// });
+/// assert_eq!(total, 4);
+/// ```
+///
+/// Whether the type has to implement `Default` only depends on how the body is written, never on
+/// how many times it runs. An iterator which never calls the closure skips the body entirely and the
+/// loop evaluates to whatever the iterator completed with.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// struct Reply(String);  // Not `Default`
+///
+/// fn ask_if<B>(
+///     enabled: bool,
+///     mut f: impl FnMut(&str) -> ControlFlow<B, Reply>,
+/// ) -> ControlFlow<B, Option<String>> {
+///     if !enabled {
+///         return ControlFlow::Continue(None);
+///     }
+///     let Reply(reply) = f("name?")?;
+///     ControlFlow::Continue(Some(reply))
+/// }
+///
+/// let mut asked = 0;
+/// let mut ask = |enabled| cbit::cbit!(for question in ask_if(enabled) {
+///     asked += 1;
+///     if question.is_empty() {
+///         break None;
+///     }
+///     Reply(format!("answer to {question}"))
+/// });
+///
+/// assert_eq!(ask(false), None);
+/// assert_eq!(ask(true), Some("answer to name?".to_string()));
+/// drop(ask);
+/// assert_eq!(asked, 1);
+/// ```
+///
+/// The `Continue` parameter on the return type of the iterator function, meanwhile, can be used to
+/// return values from the `cbit!` macro expression. If users `break` out of loops with a non-unit
+/// output `Continue` type, they must provide this value themself.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn demo(list: &[i32]) -> i32 {
+///     cbit::cbit!(for (accum, value) in reduce(0, list) {
+///         if *value > 100 {
+///             break -1;
+///         }
+///         accum + value
+///     })
+/// }
+///
+/// fn reduce<T, I: IntoIterator, B>(
+///     initial: T,
+///     values: I,
+///     mut f: impl FnMut((T, I::Item)) -> ControlFlow<B, T>,
+/// ) -> ControlFlow<B, T> {
+///     let mut accum = initial;
+///     for value in values {
+///         accum = f((accum, value))?;
+///     }
+///     ControlFlow::Continue(accum)
+/// }
+///
+/// assert_eq!(demo(&[1, 2, 3]), 6);
+/// assert_eq!(demo(&[1, 2, 3, 4, 101, 8]), -1);
+/// ```
+///
+/// Since the loop evaluates to either of them, the values given to `break` and the value with
+/// which the iterator completes always have to share a type. A mismatch is reported as
+/// incompatible `match` arms on the iterator call.
+///
+/// ```compile_fail
+/// # use std::ops::ControlFlow;
+/// # fn reduce<T, I: IntoIterator, B>(
+/// #     initial: T,
+/// #     values: I,
+/// #     mut f: impl FnMut((T, I::Item)) -> ControlFlow<B, T>,
+/// # ) -> ControlFlow<B, T> {
+/// #     let mut accum = initial;
+/// #     for value in values {
+/// #         accum = f((accum, value))?;
+/// #     }
+/// #     ControlFlow::Continue(accum)
+/// # }
+/// let total = cbit::cbit!(for (accum, value) in reduce(0i32, &[1, 2, 3]) {
+///     //                                        ^^^^^^ error: expected `&str`, found `i32`
+///     if *value > 100 {
+///         break "too big";
+///     }
+///     accum + value
+/// });
+/// ```
+///
+/// Since the `Break` parameter is generic, the iterator function can't break out of the loop with
+/// a value of its own. Every `Break` it returns is one it got from the closure, which is how the
+/// body's `break`s, `return`s, and jumps to external labels get out of it. An iterator which wants
+/// to stop the loop with a value instead completes early with `ControlFlow::Continue(value)`. The
+/// loop then evaluates to that value just like it would to the value of a `break` in the body.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// // Stops on its own once the budget runs out.
+/// fn spend<B>(budget: u32, costs: &[u32], mut f: impl FnMut(u32) -> ControlFlow<B>) -> ControlFlow<B, &'static str> {
+///     let mut left = budget;
+///     for &cost in costs {
+///         let Some(rest) = left.checked_sub(cost) else {
+///             return ControlFlow::Continue("out of budget");
+///         };
+///         left = rest;
+///         f(cost)?;
+///     }
+///     ControlFlow::Continue("done")
+/// }
+///
+/// let outcome = |costs: &[u32]| {
+///     cbit::cbit!(for cost in spend(10, costs) {
+///         if cost == 0 {
+///             break "free item";
+///         }
+///     })
+/// };
+///
+/// assert_eq!(outcome(&[3, 4]), "done");
+/// assert_eq!(outcome(&[3, 4, 5]), "out of budget");
+/// assert_eq!(outcome(&[3, 0, 5]), "free item");
+/// ```
+///
+/// An iterator function which fixes the `Break` parameter to one of its own types is rejected.
+///
+/// ```compile_fail
+/// use std::ops::ControlFlow;
+///
+/// fn spend(budget: u32, mut f: impl FnMut(u32) -> ControlFlow<u32>) -> ControlFlow<u32> {
+///     if budget == 0 {
+///         return ControlFlow::Break(0);
+///     }
+///     f(budget)
+/// }
+///
+/// cbit::cbit!(for cost in spend(10) {  // error: mismatched types
+///     println!("{cost}");
+/// });
+/// ```
+///
+/// The iterator function's generic parameters can be given explicitly with a turbofish, for
+/// example to pin the accumulator's type when it would otherwise be ambiguous. The `Break`
+/// parameter, however, is instantiated with a type internal to the macro's expansion and must be
+/// left to inference with `_`.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn reduce<T, I: IntoIterator, B>(
+/// #     initial: T,
+/// #     values: I,
+/// #     mut f: impl FnMut((T, I::Item)) -> ControlFlow<B, T>,
+/// # ) -> ControlFlow<B, T> {
+/// #     let mut accum = initial;
+/// #     for value in values {
+/// #         accum = f((accum, value))?;
+/// #     }
+/// #     ControlFlow::Continue(accum)
+/// # }
+/// let total = cbit::cbit!(for (accum, value) in reduce::<f64, Vec<f64>, _>(0.0, vec![1.0, 2.5]) {
+///     accum + value
+/// });
+/// assert_eq!(total, 3.5);
+/// ```
+///
+/// Iterator functions which wrap their `ControlFlow` object in some other type (e.g. a newtype or a
+/// `Box`) can still be used by passing an adapter function to an `unwrap(...)` clause after the
+/// call. The adapter receives the value returned by the iterator function and must produce the
+/// `ControlFlow` object described above.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// struct Walk<B>(ControlFlow<B>);
+///
+/// fn walk<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> Walk<B> {
+///     for i in 0..n {
+///         if let ControlFlow::Break(b) = f(i) {
+///             return Walk(ControlFlow::Break(b));
+///         }
+///     }
+///     Walk(ControlFlow::Continue(()))
+/// }
+///
+/// let mut sum = 0;
+/// cbit::cbit!(for i in walk(10) unwrap(|walk| walk.0) {
+///     if i == 5 {
+///         break;
+///     }
+///     sum += i;
+/// });
+/// assert_eq!(sum, 10);
+/// ```
+///
+/// Only `ControlFlow` itself is accepted as a return value without an adapter. Anything else,
+/// including references to and smart pointers around a `ControlFlow`, is rejected with an error
+/// pointing at the call and suggesting an `unwrap(...)` clause such as `unwrap(|flow| *flow)`.
+///
+/// ```compile_fail
+/// use std::ops::ControlFlow;
+///
+/// fn boxed<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> Box<ControlFlow<B>> {
+///     for i in 0..n {
+///         if let ControlFlow::Break(b) = f(i) {
+///             return Box::new(ControlFlow::Break(b));
+///         }
+///     }
+///     Box::new(ControlFlow::Continue(()))
+/// }
+///
+/// cbit::cbit!(for i in boxed(10) {  // error: `cbit!` iterators must return a `ControlFlow`...
+///     println!("{i}");
+/// });
+/// ```
+#[proc_macro]
+pub fn cbit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = syn::parse_macro_input!(input as CbitForExpr);
+    expand_cbit(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Runs a [`cbit!`](crate::cbit!) loop over the elements of an iterable, written exactly like a
+/// regular `for` loop.
+///
+/// ```text
+/// cbit::for_iter!(('<loop-label: lifetime>:)? for <binding: pattern> in <iter: expr> {
+///     <body: token stream>
+/// })
+/// ```
+///
+/// This is meant for converting existing loops in code which otherwise uses `cbit!`: wrapping a
+/// `for` loop over an [`IntoIterator`](::core::iter::IntoIterator) in `for_iter!` keeps its syntax
+/// and behavior while running its body through the same machinery as every other `cbit!` loop. It
+/// compiles down to the same code as the original loop. `break`, `continue`, `return`, and `?`
+/// behave as they do in a regular `for` loop, including when they target the loop's label.
+///
+/// ```
+/// fn native(values: &[i32]) -> (Vec<i32>, Option<i32>) {
+///     let mut seen = Vec::new();
+///     let mut stopped_at = None;
+///     'values: for &v in values {
+///         if v < 0 {
+///             continue 'values;
+///         }
+///         if v > 100 {
+///             stopped_at = Some(v);
+///             break;
+///         }
+///         seen.push(v);
+///     }
+///     (seen, stopped_at)
+/// }
+///
+/// fn converted(values: &[i32]) -> (Vec<i32>, Option<i32>) {
+///     let mut seen = Vec::new();
+///     let mut stopped_at = None;
+///     cbit::for_iter!('values: for &v in values {
+///         if v < 0 {
+///             continue 'values;
+///         }
+///         if v > 100 {
+///             stopped_at = Some(v);
+///             break;
+///         }
+///         seen.push(v);
+///     });
+///     (seen, stopped_at)
+/// }
+///
+/// for values in [&[][..], &[1, -2, 3], &[4, 500, 6, -7]] {
+///     assert_eq!(native(values), converted(values));
+/// }
+///
+/// fn first_word_len(text: &str) -> Result<Option<usize>, String> {
+///     cbit::for_iter!(for (i, word) in text.split(' ').enumerate() {
+///         if word.is_empty() {
+///             return Err(format!("empty word at {i}"));
+///         }
+///         if word.chars().all(char::is_alphabetic) {
+///             return Ok(Some(word.len()));
+///         }
+///     });
+///     Ok(None)
+/// }
+///
+/// assert_eq!(first_word_len("12 abc d"), Ok(Some(3)));
+/// assert_eq!(first_word_len("12 34"), Ok(None));
+/// assert_eq!(first_word_len("12  abc"), Err("empty word at 1".to_string()));
+/// ```
+///
+/// Like in any `cbit!` loop, labels declared outside of the macro can't be targeted from the body.
+/// Loops which need to do so should be converted to a [`cbit!`](crate::cbit!) loop declaring them.
+#[proc_macro]
+pub fn for_iter(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as ForIterArgs);
+    helpers::for_iter(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Runs a [`cbit!`](crate::cbit!) loop over a range using a built-in iterator function.
+///
+/// ```text
+/// cbit::for_range!(<range: expr>, (step <step: expr>,)? |<binding: pattern>| <body: expr>)
+/// ```
+///
+/// This is equivalent to a `cbit!` loop driven by an `up_to`-style iterator function over `range`
+/// (or over `range.step_by(step)` if a step is given) and compiles down to the same code as a
+/// regular `for` loop over the range. Any [`Iterator`](::core::iter::Iterator) can be passed as the
+/// range, including exclusive and inclusive ranges.
+///
+/// Although it is written like a closure, the body is a loop body: `break` and `continue` affect the
+/// loop and `return` and `?` exit the enclosing function.
+///
+/// ```
+/// let mut sum = 0;
+/// cbit::for_range!(0..5, |i| sum += i);
+/// assert_eq!(sum, 10);
+///
+/// let mut evens = Vec::new();
+/// cbit::for_range!(0..=10, step 2, |i: u32| {
+///     if i > 6 {
+///         break;
+///     }
+///     evens.push(i);
+/// });
+/// assert_eq!(evens, [0, 2, 4, 6]);
+///
+/// fn first_square_above(min: u64) -> Option<u64> {
+///     cbit::for_range!(1.., |i: u64| {
+///         if i * i > min {
+///             return Some(i * i);
+///         }
+///     });
+///     None
+/// }
+/// assert_eq!(first_square_above(50), Some(64));
+/// ```
+#[proc_macro]
+pub fn for_range(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as ForRangeArgs);
+    helpers::for_range(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Runs a [`cbit!`](crate::cbit!) loop over the elements of an iterable along with their indices
+/// using a built-in iterator function.
+///
+/// ```text
+/// cbit::enumerate!(<iter: expr>, |<binding: pattern>| <body: expr>)
+/// ```
+///
+/// This is the `cbit!` equivalent of a regular `for` loop over `iter.into_iter().enumerate()` and
+/// compiles down to the same code. Slices are iterated by passing a reference to them. As with
+/// [`for_range!`](crate::for_range!), the body is a loop body rather than a closure.
+///
+/// ```
+/// let names = ["a", "b", "c", "d"];
+///
+/// let mut seen = Vec::new();
+/// cbit::enumerate!(&names, |(i, &name)| {
+///     if i == 3 {
+///         break;
+///     }
+///     seen.push(format!("{i}{name}"));
+/// });
+/// assert_eq!(seen, ["0a", "1b", "2c"]);
+///
+/// fn index_of(names: &[&str], needle: &str) -> Option<usize> {
+///     cbit::enumerate!(names, |(i, name)| {
+///         if *name == needle {
+///             return Some(i);
+///         }
+///     });
+///     None
+/// }
+/// assert_eq!(index_of(&names, "c"), Some(2));
+/// assert_eq!(index_of(&names, "e"), None);
+/// ```
+#[proc_macro]
+pub fn enumerate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as EnumerateArgs);
+    helpers::enumerate(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Runs a [`cbit!`](crate::cbit!) loop over pairs of elements from two iterables in lockstep using a
+/// built-in iterator function.
+///
+/// ```text
+/// cbit::zip!(<left: expr>, <right: expr>, |<binding: pattern>| <body: expr>)
+/// ```
+///
+/// This is the `cbit!` equivalent of a regular `for` loop over `left.into_iter().zip(right)` and
+/// compiles down to the same code. The loop stops once either iterable runs out of elements. As
+/// with [`for_range!`](crate::for_range!), the body is a loop body rather than a closure.
+///
+/// ```
+/// let xs = [1, 2, 3];
+/// let ys = [10, 20, 30, 40];
+///
+/// let mut dot = 0;
+/// cbit::zip!(&xs, &ys, |(x, y)| dot += x * y);
+/// assert_eq!(dot, 140);
+///
+/// let mut out = [0; 3];
+/// cbit::zip!(&mut out, xs, |(slot, x)| {
+///     if x == 3 {
+///         continue;
+///     }
+///     *slot = x * x;
+/// });
+/// assert_eq!(out, [1, 4, 0]);
+/// ```
+#[proc_macro]
+pub fn zip(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as ZipArgs);
+    helpers::zip(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Runs a [`cbit!`](crate::cbit!) loop over the overlapping fixed-size windows of a slice using a
+/// built-in iterator function.
+///
+/// ```text
+/// cbit::windows!(<slice: expr>, <size: const expr>, |<binding: pattern>| <body: expr>)
+/// ```
+///
+/// Each window is passed to the body as a `&[T; size]`, so `size` must be usable as an array length
+/// and the window can be destructured with an array pattern. Slices shorter than `size` produce no
+/// windows. Like [`slice::windows`], this panics if `size` is zero. As with
+/// [`for_range!`](crate::for_range!), the body is a loop body rather than a closure.
+///
+/// ```
+/// let values = [1, 3, 6, 10, 15];
+///
+/// let mut deltas = Vec::new();
+/// cbit::windows!(&values, 2, |&[a, b]| deltas.push(b - a));
+/// assert_eq!(deltas, [2, 3, 4, 5]);
+///
+/// fn first_peak(values: &[i32]) -> Option<i32> {
+///     cbit::windows!(values, 3, |&[a, b, c]| {
+///         if b > a && b > c {
+///             return Some(b);
+///         }
+///     });
+///     None
+/// }
+/// assert_eq!(first_peak(&[1, 4, 2, 5, 3]), Some(4));
+/// assert_eq!(first_peak(&values), None);
+///
+/// let mut count = 0;
+/// cbit::windows!(&values[..2], 3, |_| count += 1);
+/// assert_eq!(count, 0);
+/// ```
+#[proc_macro]
+pub fn windows(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as WindowsArgs);
+    helpers::windows(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Runs a [`cbit!`](crate::cbit!) loop over the non-overlapping chunks of a slice using a built-in
+/// iterator function.
+///
+/// ```text
+/// cbit::chunks!(<slice: expr>, <size: expr>, |<binding: pattern>| <body: expr>)
+/// ```
+///
+/// Each chunk is passed to the body as a `&[T]` of `size` elements except for the last one, which
+/// is shorter if the slice's length isn't a multiple of `size`. Empty slices produce no chunks.
+/// Like [`slice::chunks`], this panics if `size` is zero. As with [`for_range!`](crate::for_range!),
+/// the body is a loop body rather than a closure.
+///
+/// ```
+/// let values = [1, 2, 3, 4, 5, 6, 7];
+///
+/// let mut sums = Vec::new();
+/// cbit::chunks!(&values, 3, |chunk| sums.push(chunk.iter().sum::<i32>()));
+/// assert_eq!(sums, [6, 15, 7]);
+///
+/// let mut count = 0;
+/// cbit::chunks!(&values[..0], 3, |_| count += 1);
+/// assert_eq!(count, 0);
+///
+/// fn first_short_chunk(values: &[i32], size: usize) -> Option<&[i32]> {
+///     cbit::chunks!(values, size, |chunk| {
+///         if chunk.len() < size {
+///             return Some(chunk);
+///         }
+///     });
+///     None
+/// }
+/// assert_eq!(first_short_chunk(&values, 2), Some(&[7][..]));
+/// assert_eq!(first_short_chunk(&values, 7), None);
+/// ```
+#[proc_macro]
+pub fn chunks(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input with WindowsArgs::parse_chunks);
+    helpers::chunks(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator until the provided function maps an element to `Some`, analogous to
+/// [`Iterator::find_map`](::std::iter::Iterator::find_map).
+///
+/// ```text
+/// cbit::find_map!(<iterator: function-call-expr>, <mapper: expr>)
+/// ```
+///
+/// The macro evaluates to `Some` of the first mapped value or to `None` if the iterator completed
+/// without any element mapping to `Some`. The iterator follows the regular
+/// [iteration protocol](crate::cbit!#iteration-protocol) and must complete with a unit `Continue`
+/// value.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn scan<'a, B>(data: &'a [&'a str], mut f: impl FnMut(&'a str) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for &v in data {
+///         f(v)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// let data = ["a", "b", "3", "4"];
+/// assert_eq!(cbit::find_map!(scan(&data), |x| x.parse::<u32>().ok()), Some(3));
+///
+/// let data = ["a", "b", "c"];
+/// assert_eq!(cbit::find_map!(scan(&data), |x| x.parse::<u32>().ok()), None);
+/// ```
+#[proc_macro]
+pub fn find_map(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as CallAndFn);
+    helpers::find_map(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator until the provided predicate returns `true` for an element, analogous to
+/// [`Iterator::position`](::std::iter::Iterator::position).
+///
+/// ```text
+/// cbit::position!(<iterator: function-call-expr>, <predicate: expr>)
+/// ```
+///
+/// The macro evaluates to `Some` of the zero-based index of the first matching element or to `None`
+/// if the iterator completed without any element matching. Indices count the elements produced by
+/// the iterator, not those of whatever collection it may be walking. The iterator follows the
+/// regular [iteration protocol](crate::cbit!#iteration-protocol) and must complete with a unit
+/// `Continue` value.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn scan<'a, B>(data: &'a [u32], mut f: impl FnMut(u32) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for &v in data {
+///         f(v)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// fn evens<'a, B>(data: &'a [u32], mut f: impl FnMut(u32) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for &v in data {
+///         if v % 2 == 0 {
+///             f(v)?;
+///         }
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// let data = [1, 2, 3, 4, 5, 6];
+/// assert_eq!(cbit::position!(scan(&data), |x| x > 10), None);
+/// assert_eq!(cbit::position!(scan(&data), |x| x == 1), Some(0));
+/// assert_eq!(cbit::position!(scan(&data), |x| x > 3), Some(3));
+/// assert_eq!(cbit::position!(evens(&data), |x| x > 3), Some(1));
+/// ```
+#[proc_macro]
+pub fn position(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as CallAndFn);
+    helpers::position(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator of numbers and adds them together, analogous to
+/// [`Iterator::sum`](::std::iter::Iterator::sum).
+///
+/// ```text
+/// cbit::sum!(<iterator: function-call-expr>)
+/// ```
+///
+/// The elements are combined with `+` and the macro evaluates to the identity given by the
+/// element type's [`Sum`](::std::iter::Sum) implementation (e.g. `0` for integers) if the iterator
+/// completed without producing any elements. The elements must be owned numbers rather than
+/// references to them. The iterator follows the regular
+/// [iteration protocol](crate::cbit!#iteration-protocol) and must complete with a unit `Continue`
+/// value.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// fn halves<B>(n: u64, mut f: impl FnMut(f64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(0.5f64.powi(i as i32 + 1))?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// assert_eq!(cbit::sum!(up_to(5)), 10);
+/// assert_eq!(cbit::sum!(up_to(0)), 0);
+/// assert_eq!(cbit::sum!(halves(3)), 0.875);
+/// assert_eq!(cbit::sum!(halves(0)), 0.0);
+/// ```
+#[proc_macro]
+pub fn sum(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as AnyCallExpr);
+    helpers::fold_numeric(input, helpers::NumericFold::Sum)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator of numbers and multiplies them together, analogous to
+/// [`Iterator::product`](::std::iter::Iterator::product).
+///
+/// ```text
+/// cbit::product!(<iterator: function-call-expr>)
+/// ```
+///
+/// The elements are combined with `*` and the macro evaluates to the identity given by the
+/// element type's [`Product`](::std::iter::Product) implementation (e.g. `1` for integers) if the
+/// iterator completed without producing any elements. The elements must be owned numbers rather
+/// than references to them. The iterator follows the regular
+/// [iteration protocol](crate::cbit!#iteration-protocol) and must complete with a unit `Continue`
+/// value.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn range<B>(lo: u64, hi: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in lo..hi {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// fn halves<B>(n: u64, mut f: impl FnMut(f64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(0.5f64.powi(i as i32 + 1))?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// assert_eq!(cbit::product!(range(1, 6)), 120);
+/// assert_eq!(cbit::product!(range(1, 1)), 1);
+/// assert_eq!(cbit::product!(halves(2)), 0.125);
+/// assert_eq!(cbit::product!(halves(0)), 1.0);
+/// ```
+#[proc_macro]
+pub fn product(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as AnyCallExpr);
+    helpers::fold_numeric(input, helpers::NumericFold::Product)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator while threading an accumulator through the provided function, analogous
+/// to [`Iterator::fold`](::std::iter::Iterator::fold).
+///
+/// ```text
+/// cbit::fold!(<iterator: function-call-expr>, <init: expr>, <folder: expr>)
+/// ```
+///
+/// The folder is called with the current accumulator and each element in turn and returns the next
+/// accumulator. The macro evaluates to the final accumulator, which is `init` itself if the iterator
+/// completed without producing any elements. The iterator follows the regular
+/// [iteration protocol](crate::cbit!#iteration-protocol) and must complete with a unit `Continue`
+/// value.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// assert_eq!(cbit::fold!(up_to(5), 0u64, |acc, x| acc + x), 10);
+/// assert_eq!(cbit::fold!(up_to(0), 7u64, |acc, x| acc * x), 7);
+///
+/// let digits = cbit::fold!(up_to(4), String::new(), |mut acc, x| {
+///     acc.push_str(&x.to_string());
+///     acc
+/// });
+/// assert_eq!(digits, "0123");
+/// ```
+///
+/// Since the folder is a regular function, `return`, `break`, and `?` inside of it cannot end the
+/// fold early. Use a [`cbit!`](crate::cbit!) loop over a `mut` accumulator for that instead.
+#[proc_macro]
+pub fn fold(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as CallInitAndFn);
+    helpers::fold(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator while threading an accumulator through the provided fallible function,
+/// stopping at the first error, analogous to
+/// [`Iterator::try_fold`](::std::iter::Iterator::try_fold).
+///
+/// ```text
+/// cbit::try_fold!(<iterator: function-call-expr>, <init: expr>, <folder: expr>)
+/// ```
+///
+/// The folder is called with the current accumulator and each element in turn and returns a
+/// `Result` of the next accumulator. The macro evaluates to `Ok` of the final accumulator or to the
+/// first `Err` returned by the folder, in which case the iterator is broken out of and no further
+/// elements are visited. The iterator follows the regular
+/// [iteration protocol](crate::cbit!#iteration-protocol) and must complete with a unit `Continue`
+/// value.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn scan<'a, B>(data: &'a [&'a str], mut f: impl FnMut(&'a str) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for &v in data {
+///         f(v)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// let mut visited = 0;
+/// let mut sum_all = |data| {
+///     cbit::try_fold!(scan(data), 0u32, |acc, x: &str| {
+///         visited += 1;
+///         Ok::<_, std::num::ParseIntError>(acc + x.parse::<u32>()?)
+///     })
+/// };
+///
+/// assert_eq!(sum_all(&["1", "2", "3"]), Ok(6));
+/// assert!(sum_all(&["1", "2", "x", "4"]).is_err());
+/// drop(sum_all);
+/// assert_eq!(visited, 6);
+///
+/// let lengths = cbit::try_fold!(scan(&["ab", "cde"]), Vec::new(), |mut acc, x: &str| {
+///     if x.is_empty() {
+///         return Err("empty element");
+///     }
+///     acc.push(x.len());
+///     Ok(acc)
+/// });
+/// assert_eq!(lengths, Ok(vec![2, 3]));
+/// ```
+#[proc_macro]
+pub fn try_fold(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as CallInitAndFn);
+    helpers::try_fold(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a fallible iterator which reports errors by breaking with them, turning its result into a
+/// [`Result`](::core::result::Result).
+///
+/// ```text
+/// cbit::try_drive!(<iterator: function-call-expr>, <handler: expr>)
+/// ```
+///
+/// Unlike the regular [iteration protocol](crate::cbit!#iteration-protocol), the iterator's
+/// `Break` type doesn't have to be generic: it can be the iterator's error type, which it breaks
+/// with when it fails. The handler is called with each element and returns a `Result<(), E>` of
+/// that same error type, where an `Err` stops the iterator as if it had failed itself. The macro
+/// evaluates to `Ok` of the value the iterator completed with or to `Err` of the error it was
+/// broken out of with, whether that error came from the iterator or from the handler.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct ParseError {
+///     line: usize,
+/// }
+///
+/// // Calls `f` with the number on every line, breaking with an error at the first bad line and
+/// // completing with the number of lines otherwise.
+/// fn parse_all(
+///     text: &str,
+///     mut f: impl FnMut(i64) -> ControlFlow<ParseError>,
+/// ) -> ControlFlow<ParseError, usize> {
+///     for (line, value) in text.lines().enumerate() {
+///         match value.trim().parse() {
+///             Ok(value) => f(value)?,
+///             Err(_) => return ControlFlow::Break(ParseError { line }),
+///         }
+///     }
+///     ControlFlow::Continue(text.lines().count())
+/// }
+///
+/// let mut sum = 0;
+/// assert_eq!(cbit::try_drive!(parse_all("1\n2\n 3"), |v| {
+///     sum += v;
+///     Ok(())
+/// }), Ok(3));
+/// assert_eq!(sum, 6);
+///
+/// let mut sum = 0;
+/// assert_eq!(cbit::try_drive!(parse_all("1\ntwo\n3"), |v| {
+///     sum += v;
+///     Ok(())
+/// }), Err(ParseError { line: 1 }));
+/// assert_eq!(sum, 1);
+///
+/// // The handler can fail with the same error type, stopping the iterator.
+/// let mut seen = Vec::new();
+/// assert_eq!(cbit::try_drive!(parse_all("1\n-2\nthree"), |v| {
+///     if v < 0 {
+///         return Err(ParseError { line: seen.len() });
+///     }
+///     seen.push(v);
+///     Ok(())
+/// }), Err(ParseError { line: 1 }));
+/// assert_eq!(seen, [1]);
+/// ```
+///
+/// Iterators following the regular protocol work as well, in which case only the handler can fail.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// assert_eq!(cbit::try_drive!(up_to(5), |i| if i < 3 { Ok(()) } else { Err(i) }), Err(3));
+/// assert_eq!(cbit::try_drive!(up_to(2), |i| if i < 3 { Ok(()) } else { Err(i) }), Ok(()));
+/// ```
+#[proc_macro]
+pub fn try_drive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as CallAndFn);
+    helpers::try_drive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator to completion, counting its elements and timing the whole pass.
+///
+/// ```text
+/// cbit::timed!(<iterator: function-call-expr>)
+/// ```
+///
+/// The macro evaluates to a `(usize, Duration)` tuple of the number of elements the iterator
+/// produced and the [`Duration`](::std::time::Duration) it took to produce them, as measured by
+/// an [`Instant`](::std::time::Instant) taken right before the iterator is called. The elements
+/// themselves are dropped right away. The iterator follows the regular
+/// [iteration protocol](crate::cbit!#iteration-protocol) and must complete with a unit `Continue`
+/// value.
+///
+/// This macro is only available with the `std` feature, which is enabled by default.
+///
+/// ```
+/// use std::{ops::ControlFlow, time::Duration};
+///
+/// fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// let (count, elapsed) = cbit::timed!(up_to(1000));
+/// assert_eq!(count, 1000);
+/// assert!(elapsed >= Duration::ZERO);
+///
+/// assert_eq!(cbit::timed!(up_to(0)).0, 0);
+/// ```
+#[cfg(feature = "std")]
+#[proc_macro]
+pub fn timed(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as AnyCallExpr);
+    helpers::timed(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator to completion, counting its elements and keeping the first and last of
+/// them.
+///
+/// ```text
+/// cbit::stats!(<iterator: function-call-expr>)
+/// ```
+///
+/// The macro evaluates to a `(usize, Option<T>, Option<T>)` tuple of the number of elements the
+/// iterator produced along with the first and last of them, both of which are `None` if the
+/// iterator produced nothing. Only the first element is cloned, so `T` must implement
+/// [`Clone`](::core::clone::Clone), and all others but the last are dropped right away. The
+/// iterator follows the regular [iteration protocol](crate::cbit!#iteration-protocol) and must
+/// complete with a unit `Continue` value.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// fn words<'a, B>(text: &'a str, mut f: impl FnMut(&'a str) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for word in text.split_whitespace() {
+///         f(word)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// assert_eq!(cbit::stats!(up_to(5)), (5, Some(0), Some(4)));
+/// assert_eq!(cbit::stats!(up_to(1)), (1, Some(0), Some(0)));
+/// assert_eq!(cbit::stats!(up_to(0)), (0, None, None));
+///
+/// let (count, first, last) = cbit::stats!(words("the quick brown fox"));
+/// assert_eq!((count, first, last), (4, Some("the"), Some("fox")));
+/// ```
+#[proc_macro]
+pub fn stats(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as AnyCallExpr);
+    helpers::stats(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator to completion and returns the last element it produced, analogous to
+/// [`Iterator::last`](::core::iter::Iterator::last).
+///
+/// ```text
+/// cbit::last!(<iterator: function-call-expr>)
+/// ```
+///
+/// The macro evaluates to `Some` of the last element or to `None` if the iterator produced nothing.
+/// Each element is moved in place of the previous one as it arrives so `T` doesn't need to
+/// implement [`Clone`](::core::clone::Clone) and every element but the last is dropped right away.
+/// The iterator follows the regular [iteration protocol](crate::cbit!#iteration-protocol) and must
+/// complete with a unit `Continue` value.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// fn lines<B>(text: &str, mut f: impl FnMut(String) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for line in text.lines() {
+///         f(line.trim().to_uppercase())?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// assert_eq!(cbit::last!(up_to(5)), Some(4));
+/// assert_eq!(cbit::last!(up_to(0)), None);
+///
+/// assert_eq!(cbit::last!(lines("first\n  second  ")), Some("SECOND".to_string()));
+/// assert_eq!(cbit::last!(lines("")), None);
+/// ```
+#[proc_macro]
+pub fn last(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as AnyCallExpr);
+    helpers::last(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator to completion and returns the element which is the smallest according to
+/// the provided comparator, analogous to [`Iterator::min_by`](::core::iter::Iterator::min_by).
+///
+/// ```text
+/// cbit::min_by!(<iterator: function-call-expr>, <comparator: expr>)
+/// ```
+///
+/// The comparator is called with references to the smallest element seen so far and to the next
+/// element and returns their [`Ordering`](::core::cmp::Ordering). The macro evaluates to `Some` of
+/// the smallest element or to `None` if the iterator produced nothing. If several elements are
+/// equally small, the first of them is returned. Only the running minimum is kept around so `T`
+/// doesn't need to implement [`Clone`](::core::clone::Clone). The iterator follows the regular
+/// [iteration protocol](crate::cbit!#iteration-protocol) and must complete with a unit `Continue`
+/// value.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn scan<B>(data: &[i32], mut f: impl FnMut(i32) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for &v in data {
+///         f(v)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// fn words<'a, B>(text: &'a str, mut f: impl FnMut(&'a str) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for word in text.split_whitespace() {
+///         f(word)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// assert_eq!(cbit::min_by!(scan(&[3, -1, 4, -1, 5]), |a, b| a.cmp(b)), Some(-1));
+/// assert_eq!(cbit::min_by!(scan(&[]), |a, b| a.cmp(b)), None);
+///
+/// // Ties keep the first of the shortest words.
+/// let shortest = cbit::min_by!(words("ccc aa bb d e"), |a, b| a.len().cmp(&b.len()));
+/// assert_eq!(shortest, Some("d"));
+/// ```
+#[proc_macro]
+pub fn min_by(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as CallAndFn);
+    helpers::best_by(input, helpers::BestBy::Min)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator to completion and returns the element which is the largest according to
+/// the provided comparator, analogous to [`Iterator::max_by`](::core::iter::Iterator::max_by).
+///
+/// ```text
+/// cbit::max_by!(<iterator: function-call-expr>, <comparator: expr>)
+/// ```
+///
+/// This works like [`min_by!`](crate::min_by!) except that it keeps the largest element and, if
+/// several elements are equally large, returns the last of them.
+///
+/// ```
+/// use std::{cmp::Ordering, ops::ControlFlow};
+///
+/// fn scan<B>(data: &[f64], mut f: impl FnMut(f64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for &v in data {
+///         f(v)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// fn words<'a, B>(text: &'a str, mut f: impl FnMut(&'a str) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for word in text.split_whitespace() {
+///         f(word)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// assert_eq!(cbit::max_by!(scan(&[2.5, 7.0, -1.0]), f64::total_cmp), Some(7.0));
+/// assert_eq!(cbit::max_by!(scan(&[]), f64::total_cmp), None);
+///
+/// // Ties keep the last of the longest words.
+/// let longest = cbit::max_by!(words("aa ccc bb ddd e"), |a, b| a.len().cmp(&b.len()));
+/// assert_eq!(longest, Some("ddd"));
+///
+/// // Comparators can be arbitrary functions, e.g. one reversing the order.
+/// fn reversed(a: &f64, b: &f64) -> Ordering {
+///     b.total_cmp(a)
+/// }
+/// assert_eq!(cbit::max_by!(scan(&[2.5, 7.0, -1.0]), reversed), Some(-1.0));
+/// ```
+#[proc_macro]
+pub fn max_by(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as CallAndFn);
+    helpers::best_by(input, helpers::BestBy::Max)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Drives a cbit iterator to completion and gathers its elements into a
+/// [`Vec`](::std::vec::Vec), analogous to [`Iterator::collect`](::std::iter::Iterator::collect).
+///
+/// ```text
+/// cbit::collect!(<iterator: function-call-expr>)
+/// ```
+///
+/// The elements are pushed in the order in which the iterator yields them. The iterator follows
+/// the regular [iteration protocol](crate::cbit!#iteration-protocol) and must complete with a unit
+/// `Continue` value.
+///
+/// ```
+/// use std::ops::ControlFlow;
+///
+/// fn squares<B>(n: u32, mut f: impl FnMut(u32) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(i * i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// fn words<'a, B>(text: &'a str, mut f: impl FnMut(&'a str) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for word in text.split_whitespace() {
+///         f(word)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// assert_eq!(cbit::collect!(squares(4)), [0, 1, 4, 9]);
+/// assert_eq!(cbit::collect!(squares(0)), Vec::<u32>::new());
+/// assert_eq!(cbit::collect!(words("a bc  def")), ["a", "bc", "def"]);
+/// ```
+///
+/// This macro is only available with the `std` feature, which is enabled by default, since its
+/// expansion builds a `::std::vec::Vec`.
+#[cfg(feature = "std")]
+#[proc_macro]
+pub fn collect(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as AnyCallExpr);
+    helpers::collect(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Collects the elements of a cbit iterator like [`collect!`](crate::collect!) and hands them to
+/// [`rayon`](https://docs.rs/rayon) as a parallel iterator.
+///
+/// ```text
+/// cbit::par_collect!(<iterator: function-call-expr>)
+/// ```
+///
+/// This is eager: the iterator is driven to completion on the calling thread and its elements
+/// are buffered into a `Vec` before the macro evaluates to that `Vec`'s
+/// [`IntoParallelIterator`](https://docs.rs/rayon/latest/rayon/iter/trait.IntoParallelIterator.html)
+/// conversion. Parallelism therefore only applies to whatever is chained onto the result and
+/// never to the iteration itself, which remains sequential because of the callback model.
+///
+/// This macro is only available with the `rayon` feature enabled. The expansion refers to
+/// `::rayon`, so the calling crate must depend on `rayon` itself.
+///
+/// ```ignore
+/// use std::ops::ControlFlow;
+/// use rayon::iter::ParallelIterator;
+///
+/// fn up_to<B>(n: u64, mut f: impl FnMut(u64) -> ControlFlow<B>) -> ControlFlow<B> {
+///     for i in 0..n {
+///         f(i)?;
+///     }
+///     ControlFlow::Continue(())
+/// }
+///
+/// let total: u64 = cbit::par_collect!(up_to(1000)).map(|i| i * i).sum();
+/// assert_eq!(total, 332_833_500);
+/// ```
+#[cfg(feature = "rayon")]
+#[proc_macro]
+pub fn par_collect(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as AnyCallExpr);
+    helpers::par_collect(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_cbit(mut input: CbitForExpr) -> syn::Result<TokenStream> {
+    let options = CbitOptions::from_attrs(&input.attrs)?;
+
+    // These all act on the final `match`, which a `raw` loop replaces with the user's handler.
+    if let Some(raw) = &input.raw {
+        let conflict = input
+            .kw_try
+            .as_ref()
+            .map(|kw_try| (kw_try.span, "the `try` prefix"))
+            .or(options
+                .on_break
+                .as_ref()
+                .map(|hook| (hook.span(), "`#[on_break(...)]`")))
+            .or(options
+                .numbered_breaks
+                .as_ref()
+                .map(|_| (raw.kw_raw.span, "`#[numbered_breaks]`")));
+
+        if let Some((span, what)) = conflict {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "{what} cannot be used in a `raw` loop since the loop's result is handled by \
+                     `{}` instead",
+                    raw.ident
+                ),
+            ));
+        }
+    }
+
+    // The payload of a `#[dyn_callback]` loop is stashed away by its closure, which can't be shared
+    // between chained iterators.
+    if let (true, Some(then)) = (options.dyn_callback, input.chain.first()) {
+        return Err(syn::Error::new(
+            then.kw_then.span,
+            "iterators cannot be chained with `then` in a `#[dyn_callback]` loop",
+        ));
+    }
+
+    // With `#[numbered_breaks]`, the loop must be labeled so that nested loops can reach it and it
+    // must be able to reach all of its ancestors itself.
+    let numbered_levels = options.numbered_breaks.as_ref().map(|ancestors| {
+        let own = input
+            .label
+            .get_or_insert_with(|| Label {
+                name: internal_label(&format!("depth_{}", ancestors.len() + 1), Span::call_site()),
+                colon_token: Default::default(),
+            })
+            .name
+            .clone();
+
+        let breaks = input.breaks.get_or_insert_with(|| CbitForExprBreaks {
+            kw_break: Default::default(),
+            lt: Punctuated::new(),
+        });
+
+        for ancestor in ancestors {
+            if !breaks.lt.iter().any(|v| v.lt.ident == ancestor.ident) {
+                breaks.lt.push(CbitForExprSingleBreak {
+                    kind: syntax::CbitLabelKind::Loop(Default::default()),
+                    lt: ancestor.clone(),
+                    with: None,
+                });
+            }
+        }
+
+        ancestors.iter().cloned().chain([own]).collect::<Vec<_>>()
+    });
 
     // Define some common syntax trees
     let core_ = quote! { ::core };
     let ops_ = quote! { #core_::ops };
+
+    // The iterator may use a look-alike of `ControlFlow` instead of the real thing.
+    let control_flow_ = match &options.control_flow {
+        Some(path) => quote! { #path },
+        None => quote! { #ops_::ControlFlow },
+    };
     let pin_ = quote! { #core_::pin };
     let task_ = quote! { #core_::task };
     let future_ = quote! { #core_::future };
@@ -406,11 +3410,147 @@ pub fn cbit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         .as_ref()
         .map_or(&empty_punct_list, |breaks| &breaks.lt);
 
-    let derive_early_break_variant_name =
-        |lt: &Lifetime| Ident::new(&format!("EarlyBreakTo_{}", lt.ident), lt.span());
+    // Labels which only differ in case or underscores can camel-case to the same name so those are
+    // disambiguated with a numeric suffix.
+    let mut label_variant_suffixes = Vec::<(Ident, String)>::new();
+    for v in in_break_labels {
+        let base = camel_case_label(&v.lt);
+        let mut suffix = base.clone();
+        let mut counter = 2;
+        while label_variant_suffixes
+            .iter()
+            .any(|(_, taken)| *taken == suffix)
+        {
+            suffix = format!("{base}{counter}");
+            counter += 1;
+        }
+        label_variant_suffixes.push((v.lt.ident.clone(), suffix));
+    }
+
+    let label_variant_suffix = |lt: &Lifetime| {
+        label_variant_suffixes
+            .iter()
+            .find(|(ident, _)| *ident == lt.ident)
+            .map(|(_, suffix)| suffix.clone())
+            .unwrap_or_else(|| camel_case_label(lt))
+    };
+
+    let derive_early_break_variant_name = |lt: &Lifetime| {
+        Ident::new(
+            &format!("EarlyBreakTo{}", label_variant_suffix(lt)),
+            lt.span(),
+        )
+    };
+
+    let derive_early_continue_variant_name = |lt: &Lifetime| {
+        Ident::new(
+            &format!("EarlyContinueTo{}", label_variant_suffix(lt)),
+            lt.span(),
+        )
+    };
+
+    let derive_early_continue_with_variant_name = |lt: &Lifetime| {
+        Ident::new(
+            &format!("EarlyContinueWithTo{}", label_variant_suffix(lt)),
+            lt.span(),
+        )
+    };
+
+    let derive_continue_with_label =
+        |lt: &Lifetime| internal_label(&format!("continue_with_{}", lt.ident), lt.span());
+
+    // The block producing the value handed back to the iterator for each iteration
+    let innermost_label = internal_label("innermost", Span::call_site());
+
+    // The block absorbing the new inputs which `redo with` re-runs the body with
+    let redo_label = internal_label("redo", Span::call_site());
+
+    // Rewrite `continue 'label with value` expressions into breaks to their absorber layers
+    let (for_body, may_redo) = {
+        let continue_with_labels = in_break_labels
+            .iter()
+            .filter(|v| v.with.is_some())
+            .map(|v| (&v.lt, derive_continue_with_label(&v.lt)))
+            .collect::<Vec<_>>();
+
+        let targets = continue_with_labels
+            .iter()
+            .map(|(user, internal)| (*user, internal))
+            .chain(input.label.as_ref().map(|v| (&v.name, &innermost_label)))
+            .collect::<Vec<_>>();
+
+        let for_body =
+            rewrite::rewrite_continue_with(input.body.body.clone(), &targets, &innermost_label)?;
+
+        let (for_body, may_redo) = rewrite::rewrite_redo_with(for_body, &redo_label)?;
+
+        let for_body = match in_break_labels
+            .iter()
+            .find(|v| matches!(v.kind, syntax::CbitLabelKind::Out(_)))
+        {
+            Some(out) => rewrite::rewrite_break_out(for_body, &out.lt),
+            None => for_body,
+        };
+
+        let for_body = match &numbered_levels {
+            Some(levels) => rewrite::rewrite_numbered_breaks(for_body, levels)?,
+            None => for_body,
+        };
+
+        (for_body, may_redo)
+    };
+
+    // Reject attempts to `continue` with a value outside of the `continue 'label with` form
+    scan::check_continue_values(for_body.clone())?;
+
+    // Ensure that the body only targets external labels it has declared. Bodies produced by a macro
+    // can't be inspected at all so we assume they could do anything.
+    let body_info = match &input.body.delim {
+        syntax::OpaqueBodyDelim::Macro(..) => None,
+        _ => scan::scan_body(&for_body),
+    };
+    if let Some(info) = &body_info {
+        check_foreign_labels(&input, in_break_labels, info)?;
+    }
+
+    // With `#[warn_unused_labels]`, every declared label which the body never mentions gets an
+    // empty block of the same name so that rustc's `unused_labels` lint points at its declaration.
+    // The blocks are put in a closure, which labels can't cross, so that they don't shadow the
+    // actual labels.
+    let unused_label_warnings = match &body_info {
+        Some(info) if options.warn_unused_labels => {
+            let mentioned = scan::mentioned_lifetimes(input.body.body.clone());
+            let unused = in_break_labels.iter().map(|v| &v.lt).filter(|lt| {
+                !mentioned
+                    .iter()
+                    .chain(&info.foreign_breaks)
+                    .chain(&info.foreign_continues)
+                    .any(|v| v.ident == lt.ident)
+            });
+            quote! { let _ = || { #(#unused: {})* }; }
+        }
+        _ => quote! {},
+    };
 
-    let derive_early_continue_variant_name =
-        |lt: &Lifetime| Ident::new(&format!("EarlyContinueTo_{}", lt.ident), lt.span());
+    // Plain `continue`s of the virtual loop hand a default value back to the iterator. If we can
+    // tell that there aren't any, the value's type doesn't need to implement `Default`.
+    let may_continue_plainly = body_info.as_ref().map_or(true, |info| {
+        info.may_continue_unlabeled
+            || input.label.as_ref().is_some_and(|label| {
+                info.foreign_continues
+                    .iter()
+                    .any(|lt| lt.ident == label.name.ident)
+            })
+    });
+
+    // Labels which the body never `continue`s don't need a loop to continue, nor a variant and
+    // matcher to carry that out, even if they were declared as `loop`s.
+    let is_continued = |v: &syntax::CbitForExprSingleBreak| {
+        v.accepts_continue()
+            && body_info
+                .as_ref()
+                .map_or(true, |info| info.may_continue(&v.lt))
+    };
 
     // Define an enum for our control flow
     let control_flow_enum_def;
@@ -424,65 +3564,99 @@ pub fn cbit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 
         let continue_variant_names = in_break_labels
             .iter()
-            .filter(|&v| v.kw_loop.is_some())
+            .filter(|&v| is_continued(v))
             .map(|v| derive_early_continue_variant_name(&v.lt));
 
+        // These variants carry a value so they get a generic parameter too.
+        let valued_variant_names = break_variant_names
+            .iter()
+            .cloned()
+            .chain(
+                in_break_labels
+                    .iter()
+                    .filter(|&v| v.with.is_some())
+                    .map(|v| derive_early_continue_with_variant_name(&v.lt)),
+            )
+            .collect::<Vec<_>>();
+
         control_flow_enum_def = quote! {
-            #[allow(non_camel_case_types)]
             #[allow(clippy::enum_variant_names)]
-            enum OurControlFlowResult<EarlyReturn, EarlyBreak #(, #break_variant_names)*> {
+            enum OurControlFlowResult<EarlyReturn, EarlyBreak #(, #valued_variant_names)*> {
                 EarlyReturn(EarlyReturn),
                 EarlyBreak(EarlyBreak),
-                #(#break_variant_names (#break_variant_names),)*
+                #(#valued_variant_names (#valued_variant_names),)*
                 #(#continue_variant_names,)*
             }
         };
 
+        // The `Continue` type is only tied to the type of `break` values by the final `match`, which
+        // reports mismatches between the two as incompatible arms rather than as a mismatch between
+        // two instances of our internal types. This also leaves it free for infinite iterators,
+        // which never complete, and for `try` mode, where the loop evaluates to the unwrapped
+        // `break` values instead.
         control_flow_ty_decl = quote! {
-            #[allow(non_camel_case_types)]
-            type OurControlFlow<EarlyReturn, EarlyBreak #(, #break_variant_names)*> = #ops_::ControlFlow<
-                OurControlFlowResult<EarlyReturn, EarlyBreak #(, #break_variant_names)*>,
-                EarlyBreak,
+            type OurControlFlow<EarlyReturn, EarlyBreak, Completion #(, #valued_variant_names)*> = #control_flow_<
+                OurControlFlowResult<EarlyReturn, EarlyBreak #(, #valued_variant_names)*>,
+                Completion,
             >;
         };
 
         let underscores =
-            (0..(break_variant_names.len() + 2)).map(|_| Token![_](Span::call_site()));
+            (0..(valued_variant_names.len() + 3)).map(|_| Token![_](Span::call_site()));
 
         control_flow_ty_use = quote! { OurControlFlow<#(#underscores),*> };
     }
 
-    // Define our initial break layer
+    // Every way of leaving the body other than `return` breaks out to a single dispatch block with
+    // the value the closure should resolve to. This keeps the body's state machine down to a single
+    // suspension point no matter how many external labels there are.
+    let dispatch_label = internal_label("dispatch", Span::call_site());
     let aborter = |resolution: TokenStream| {
         quote! {
-            how_to_resolve_pending = #option_::Some(#resolution);
-            #future_::pending::<()>().await;
-            #core_::unreachable!();
+            break #dispatch_label #resolution;
         }
     };
 
-    let for_body = input.body.body;
+    // With `#[dyn_callback]`, the closure's break payload is passed out through this local instead
+    // of through the iterator.
+    let dyn_break_payload = Ident::new("dyn_break_payload", Span::mixed_site());
+
+    // Define our initial break layer
+
     let for_body = {
         let optional_label = &input.label;
         let break_aborter = aborter(quote! {
-            #ops_::ControlFlow::Break(OurControlFlowResult::EarlyBreak(break_result))
+            #control_flow_::Break(OurControlFlowResult::EarlyBreak(break_result))
         });
 
+        let continue_resolution = if may_continue_plainly {
+            quote! { break #innermost_label #core_::default::Default::default(); }
+        } else {
+            quote! { #core_::unreachable!(); }
+        };
+
         quote! {
-            '__cbit_absorber_magic_innermost: {
+            #innermost_label: {
                 let mut did_run = false;
+
+                // The user's label may only be used by `continue 'label with` expressions, which we
+                // have rewritten to target the innermost block instead.
+                #[allow(unused_labels)]
                 let break_result = #optional_label loop {
                     if did_run {
                         // The user must have used `continue`.
-                        break '__cbit_absorber_magic_innermost #core_::default::Default::default();
+                        #continue_resolution
                     }
 
                     did_run = true;
+
+                    // Bodies which always `return` or `continue with` a value never complete.
+                    #[allow(clippy::diverging_sub_expression)]
                     let break_result = { #for_body };
 
                     // The user completed the loop.
                     #[allow(unreachable_code)]
-                    break '__cbit_absorber_magic_innermost break_result;
+                    break #innermost_label break_result;
                 };
 
                 // The user broke out of the loop.
@@ -494,6 +3668,27 @@ pub fn cbit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         }
     };
 
+    // The condition of a `do` loop is checked after every run of the body, including those ending
+    // in a `continue`, and leaves the loop like a plain `break` once it fails.
+    let for_body = match &input.do_while {
+        Some((_, cond)) => {
+            let break_aborter = aborter(quote! {
+                #control_flow_::Break(OurControlFlowResult::EarlyBreak(()))
+            });
+
+            quote! {{
+                let continue_value = { #for_body };
+                let keep_going: bool = #cond;
+                if keep_going {
+                    continue_value
+                } else {
+                    #break_aborter
+                }
+            }}
+        }
+        None => for_body,
+    };
+
     // Build up an onion of user-specified break layers
     let for_body = {
         let mut for_body = for_body;
@@ -503,20 +3698,52 @@ pub fn cbit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             let break_aborter = {
                 let variant_name = derive_early_break_variant_name(break_label);
                 aborter(quote! {
-                    #ops_::ControlFlow::Break(OurControlFlowResult::#variant_name(break_result))
+                    #control_flow_::Break(OurControlFlowResult::#variant_name(break_result))
                 })
             };
 
-            let outer_label = Lifetime::new(
-                &format!("'__cbit_absorber_magic_for_{}", break_label.ident),
-                break_label.span(),
-            );
+            let outer_label =
+                internal_label(&format!("for_{}", break_label.ident), break_label.span());
+
+            let layer_body = if break_label_entry.with.is_some() {
+                let continue_with_label = derive_continue_with_label(break_label);
+                let continue_with_aborter = {
+                    let variant_name = derive_early_continue_with_variant_name(break_label);
+                    aborter(quote! {
+                        #control_flow_::Break(OurControlFlowResult::#variant_name(continue_value))
+                    })
+                };
+
+                quote! {
+                    let continue_value = #continue_with_label: {
+                        let break_result = { #for_body };
+
+                        // The user completed the loop.
+                        #[allow(unreachable_code)]
+                        break #outer_label break_result;
+                    };
+
+                    // The user continued with a value.
+                    #[allow(unreachable_code)]
+                    {
+                        #continue_with_aborter
+                    }
+                }
+            } else {
+                quote! {
+                    let break_result = { #for_body };
+
+                    // The user completed the loop.
+                    #[allow(unreachable_code)]
+                    break #outer_label break_result;
+                }
+            };
 
-            if break_label_entry.kw_loop.is_some() {
+            if is_continued(break_label_entry) {
                 let continue_aborter = {
                     let variant_name = derive_early_continue_variant_name(break_label);
                     aborter(quote! {
-                        #ops_::ControlFlow::Break(OurControlFlowResult::#variant_name)
+                        #control_flow_::Break(OurControlFlowResult::#variant_name)
                     })
                 };
 
@@ -529,11 +3756,7 @@ pub fn cbit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                         }
 
                         did_run = true;
-                        let break_result = { #for_body };
-
-                        // The user completed the loop.
-                        #[allow(unreachable_code)]
-                        break #outer_label break_result;
+                        #layer_body
                     };
 
                     // The user broke out of the loop.
@@ -543,13 +3766,17 @@ pub fn cbit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                     }
                 }};
             } else {
+                // The user's label may only be used by `continue 'label with` expressions, which we
+                // have rewritten to target the absorber block instead.
+                let allow_unused = break_label_entry
+                    .with
+                    .as_ref()
+                    .map(|_| quote! { #[allow(unused_labels)] });
+
                 for_body = quote! {#outer_label: {
+                    #allow_unused
                     let break_result = #break_label: {
-                        let break_result = { #for_body };
-
-                        // The user completed the loop.
-                        #[allow(unreachable_code)]
-                        break #outer_label break_result;
+                        #layer_body
                     };
 
                     // The user broke out of the block.
@@ -564,46 +3791,201 @@ pub fn cbit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         for_body
     };
 
+    // Define the waker used to poll the body. It is never woken since we never `.await` a future
+    // which could become ready.
+    let noop_waker = if cfg!(feature = "legacy-waker") {
+        quote! {&{
+            const VTABLE: #task_::RawWakerVTable = #task_::RawWakerVTable::new(
+                // Cloning just returns a new no-op raw waker
+                |_| RAW,
+                // `wake` does nothing
+                |_| {},
+                // `wake_by_ref` does nothing
+                |_| {},
+                // Dropping does nothing as we don't allocate anything
+                |_| {},
+            );
+            const RAW: #task_::RawWaker = #task_::RawWaker::new(#core_::ptr::null(), &VTABLE);
+            unsafe { #task_::Waker::from_raw(RAW) }
+        }}
+    } else {
+        quote! { #task_::Waker::noop() }
+    };
+
+    // The `first(...)` flag is flipped by the first run of the body and stays that way for the rest
+    // of the loop.
+    let first_flag = Ident::new("is_first_run", Span::mixed_site());
+    let (first_flag_decl, first_flag_bind) = match &input.first {
+        Some(first) => {
+            let ident = &first.ident;
+            (
+                quote! { let mut #first_flag = true; },
+                quote! { let #ident = #core_::mem::replace(&mut #first_flag, false); },
+            )
+        }
+        None => (quote! {}, quote! {}),
+    };
+
     // Build up a layer to capture early returns and generally process arguments
     let for_body = {
-        let body_input_pat = &input.body_pattern;
-        let termination_aborter = aborter(quote! { #ops_::ControlFlow::Continue(end_result) });
-        quote! {
-            |#body_input_pat| {
+        let body_input = Ident::new("body_input", Span::mixed_site());
+        let (body_input_pat, body_input_assign) = match &input.binding {
+            // The pattern is bound anew for every run of the body.
+            syntax::CbitBinding::Pattern(_) if may_redo => (quote! { #body_input }, quote! {}),
+            syntax::CbitBinding::Pattern(pat) => (quote! { #pat }, quote! {}),
+            syntax::CbitBinding::Assign(target) => {
+                (quote! { #body_input }, quote! { #target = #body_input; })
+            }
+        };
+        let body_input_pat = match input.closure_elem_ty() {
+            Some(ty) => quote! { #body_input_pat: #ty },
+            None => body_input_pat,
+        };
+
+        // Errors about the closure argument as a whole (e.g. passing one argument too many because
+        // the call already provides its own callback) are reported on the span of the closure's
+        // outermost tokens, which we point at the user's callee rather than at the entire loop.
+        let callee_span = match &input.call {
+            syntax::AnyCallExpr::Function(call) => call.func.span(),
+            syntax::AnyCallExpr::Method(call) => call.method.span(),
+        };
+        let resolve_pending = match &options.on_await_misuse {
+            Some(handler) => quote! {
+                match how_to_resolve_pending {
+                    #option_::Some(resolution) => resolution,
+                    #option_::None => (#handler)(),
+                }
+            },
+            // The body always stores its resolution before it first suspends so, unless it awaits
+            // something, LLVM proves this is `Some` and drops the panic and its message.
+            None => quote! {
+                how_to_resolve_pending.expect(#core_::concat!(
+                    "the body of the `cbit!` loop at ",
+                    #core_::file!(),
+                    ":",
+                    #core_::line!(),
+                    " was suspended by an `.await`. The body runs in an `async` block as an \
+                     implementation detail of the macro and cannot wait on futures; remove the \
+                     `.await` (or the macro expanding to one) from the loop body or drive the \
+                     future to completion with an executor instead.",
+                ))
+            },
+        };
+
+        let suspend_with_resolution = quote! {
+            #[allow(unreachable_code)]
+            {
+                how_to_resolve_pending = #option_::Some(resolution);
+                #future_::pending::<()>().await;
+                // This is never resumed and LLVM removes the panic path even when the iterator
+                // isn't inlined so it isn't worth an `unreachable_unchecked` here.
+                #core_::unreachable!()
+            }
+        };
+        let dispatch = quote! {
+            let resolution = #dispatch_label: {
+                let end_result = { #for_body };
+
+                #[allow(unreachable_code)]
+                #control_flow_::Continue(end_result)
+            };
+        };
+        let poll = quote! {
+            #future_::Future::poll(body, &mut #task_::Context::from_waker(#noop_waker))
+        };
+
+        let closure_inner = if may_redo {
+            // `redo with` runs the body again with a new input without going back to the iterator
+            // so the body is polled in a loop, suspending with that input instead of a resolution.
+            let redo_input = Ident::new("redo_input", Span::mixed_site());
+            let bind_input = match &input.binding {
+                syntax::CbitBinding::Pattern(pat) => quote! { let #pat = #body_input; },
+                syntax::CbitBinding::Assign(target) => quote! { #target = #body_input; },
+            };
+
+            quote! {
+                #first_flag_bind
+                let mut #body_input = #body_input;
+
+                loop {
+                    #bind_input
+                    let mut how_to_resolve_pending = #option_::None;
+                    let mut #redo_input = #option_::None;
+
+                    let body = #pin_::pin!(async {
+                        let next_input = #redo_label: {
+                            #dispatch
+                            #suspend_with_resolution
+                        };
+
+                        #[allow(unreachable_code)]
+                        {
+                            #redo_input = #option_::Some(next_input);
+                            #future_::pending::<()>().await;
+                            #core_::unreachable!()
+                        }
+                    });
+
+                    match #poll {
+                        #task_::Poll::Ready(early_return) => break #control_flow_::Break(
+                            OurControlFlowResult::EarlyReturn(early_return),
+                        ),
+                        #task_::Poll::Pending => match #redo_input {
+                            #option_::Some(next_input) => #body_input = next_input,
+                            #option_::None => break #resolve_pending,
+                        },
+                    }
+                }
+            }
+        } else {
+            quote! {
+                #body_input_assign
+                #first_flag_bind
                 let mut how_to_resolve_pending = #option_::None;
 
                 let body = #pin_::pin!(async {
-                    let end_result = { #for_body };
-
-                    #[allow(unreachable_code)] { #termination_aborter }
+                    #dispatch
+                    #suspend_with_resolution
                 });
 
-                match #future_::Future::poll(
-                    body,
-                    &mut #task_::Context::from_waker(&{  // TODO: Use `Waker::noop` once it stabilizes
-                        const VTABLE: #task_::RawWakerVTable = #task_::RawWakerVTable::new(
-                            // Cloning just returns a new no-op raw waker
-                            |_| RAW,
-                            // `wake` does nothing
-                            |_| {},
-                            // `wake_by_ref` does nothing
-                            |_| {},
-                            // Dropping does nothing as we don't allocate anything
-                            |_| {},
-                        );
-                        const RAW: #task_::RawWaker = #task_::RawWaker::new(#core_::ptr::null(), &VTABLE);
-                        unsafe { #task_::Waker::from_raw(RAW) }
-                    })
-                ) {
-                    #task_::Poll::Ready(early_return) => #ops_::ControlFlow::Break(
+                match #poll {
+                    #task_::Poll::Ready(early_return) => #control_flow_::Break(
                         OurControlFlowResult::EarlyReturn(early_return),
                     ),
-                    #task_::Poll::Pending => how_to_resolve_pending.expect(
-                        "the async block in a cbit iterator is an implementation detail; do not \
-                         `.await` in it!"
-                    ),
+                    #task_::Poll::Pending => #resolve_pending,
                 }
             }
+        };
+
+        if options.dyn_callback {
+            // The break payload is stashed away so that the iterator only ever sees `Break(())`.
+            quote_spanned! {callee_span=>
+                &mut |#body_input_pat| match { #closure_inner } {
+                    #control_flow_::Break(break_payload) => {
+                        #dyn_break_payload = #option_::Some(break_payload);
+                        #control_flow_::Break(())
+                    }
+                    #control_flow_::Continue(value) => #control_flow_::Continue(value),
+                }
+            }
+        } else {
+            quote_spanned! {callee_span=> |#body_input_pat| { #closure_inner } }
+        }
+    };
+
+    // Define a tracer to log which control-flow path was taken
+    let trace = |path: &str| {
+        if cfg!(feature = "trace") && options.trace {
+            quote! {
+                ::std::eprintln!(
+                    "[cbit {}:{}] {}",
+                    #core_::file!(),
+                    #core_::line!(),
+                    #path,
+                );
+            }
+        } else {
+            quote! {}
         }
     };
 
@@ -611,54 +3993,319 @@ pub fn cbit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let break_out_matchers = in_break_labels.iter().map(|v| {
         let lt = &v.lt;
         let variant_name = derive_early_break_variant_name(lt);
+        let trace = trace(&variant_name.to_string());
+
+        // `while` and `for` loops cannot be broken out of with a value, not even a unit one.
+        if v.accepts_break_value() {
+            quote! {
+                OurControlFlowResult::#variant_name(break_out) => {
+                    #trace
+                    break #lt break_out
+                }
+            }
+        } else {
+            quote! {
+                OurControlFlowResult::#variant_name(()) => {
+                    #trace
+                    break #lt
+                }
+            }
+        }
+    });
+
+    // We can't tell what kind of construct an external label is attached to so, if it turns out not
+    // to be a loop, rustc rejects our `continue` of it. That error should point at the clause
+    // claiming otherwise rather than at the entire loop.
+    let continue_out = |v: &syntax::CbitForExprSingleBreak| {
+        let kw_continue = Ident::new(
+            "continue",
+            v.kind.keyword_span().unwrap_or_else(|| v.lt.span()),
+        );
+        let lt = &v.lt;
+        quote! { #kw_continue #lt }
+    };
+
+    let continue_out_matchers = in_break_labels.iter().filter(|v| is_continued(v)).map(|v| {
+        let variant_name = derive_early_continue_variant_name(&v.lt);
+        let trace = trace(&variant_name.to_string());
+        let continue_out = continue_out(v);
         quote! {
-            OurControlFlowResult::#variant_name(break_out) => break #lt break_out,
+            OurControlFlowResult::#variant_name => {
+                #trace
+                #continue_out
+            }
         }
     });
 
-    let continue_out_matchers = in_break_labels
-        .iter()
-        .filter(|v| v.kw_loop.is_some())
-        .map(|v| {
-            let lt = &v.lt;
-            let variant_name = derive_early_continue_variant_name(lt);
-            quote! {
-                OurControlFlowResult::#variant_name => continue #lt,
+    let continue_with_out_matchers = in_break_labels.iter().filter_map(|v| {
+        let (_, target) = v.with.as_ref()?;
+        let variant_name = derive_early_continue_with_variant_name(&v.lt);
+        let trace = trace(&variant_name.to_string());
+        let continue_out = continue_out(v);
+        Some(quote! {
+            OurControlFlowResult::#variant_name(continue_value) => {
+                #trace
+                #target = continue_value;
+                #continue_out
             }
-        });
+        })
+    });
 
     // Build up our function call site
-    let driver_call_site = match &input.call {
-        syntax::AnyCallExpr::Function(call) => {
-            let driver_attrs = &call.attrs;
-            let driver_fn_expr = &call.func;
-            let driver_fn_args = call.args.iter();
+    // `#[cfg(...)]` attributes on the call gate the entire loop rather than just the call so that
+    // the rest of the expansion never refers to a `result` which has been configured away.
+    let (cfg_attrs, driver_attrs) = input
+        .call
+        .attrs()
+        .iter()
+        .partition::<Vec<_>, _>(|attr| attr.path().is_ident("cfg"));
+
+    // With `#[hoist_args]`, the arguments are bound to locals in their own statements so that their
+    // temporaries are dropped before the driver starts running the body.
+    let hoist_args = |call: &syntax::AnyCallExpr| {
+        let call_args = call.args();
+        if options.hoist_args {
+            let names = (0..call_args.len())
+                .map(|i| Ident::new(&format!("arg_{i}"), Span::mixed_site()))
+                .collect::<Vec<_>>();
+
+            let hoisted = call_args
+                .iter()
+                .zip(&names)
+                .map(|(arg, name)| quote! { let #name = #arg; });
+
+            (quote! { #(#hoisted)* }, quote! { #(#names,)* })
+        } else {
+            (quote! {}, {
+                let call_args = call_args.iter();
+                quote! { #(#call_args,)* }
+            })
+        }
+    };
+
+    // Iterators chained with `then` all share the same closure so it is bound to a local first.
+    let chained_body = Ident::new("chained_body", Span::mixed_site());
+    let (chained_body_decl, for_body) = if input.chain.is_empty() {
+        (quote! {}, for_body)
+    } else {
+        (
+            quote! { let mut #chained_body = #for_body; },
+            quote! { &mut #chained_body },
+        )
+    };
+
+    let build_driver_call = |call: &syntax::AnyCallExpr, driver_fn_args: TokenStream| {
+        let driver_call = match call {
+            syntax::AnyCallExpr::Function(call) => {
+                let driver_fn_expr = &call.func;
+
+                quote! { #driver_fn_expr (#driver_fn_args #for_body) }
+            }
+            syntax::AnyCallExpr::Method(call) => {
+                let driver_receiver_expr = &call.receiver;
+                let driver_method = &call.method;
+                let driver_turbo = &call.turbofish;
+
+                quote! {
+                    #driver_receiver_expr.#driver_method #driver_turbo (
+                        #driver_fn_args
+                        #for_body
+                    )
+                }
+            }
+        };
+
+        // The adapter is passed through a generic trampoline so that the parameter types of
+        // closure literals can be inferred from the value returned by the iterator.
+        let driver_call = match &input.unwrap {
+            Some(unwrap) => {
+                let adapter = &unwrap.adapter;
+                let apply = Ident::new("apply_unwrap", Span::mixed_site());
+
+                quote! {{
+                    #[inline(always)]
+                    fn #apply<T, R>(f: impl #ops_::FnOnce(T) -> R, value: T) -> R {
+                        f(value)
+                    }
 
+                    #apply(#adapter, #driver_call)
+                }}
+            }
+            None => driver_call,
+        };
+
+        // Iterators returning something other than a `ControlFlow` (e.g. a `Box` of one) would
+        // otherwise produce a type mismatch against our internal enum so we route the value through
+        // a trait with a more helpful error instead. `#[diagnostic]` attributes were stabilized in
+        // Rust 1.78, so they're left out for the older toolchains targeted by `legacy-waker`.
+        let returns_control_flow = Ident::new("ReturnsControlFlow", Span::mixed_site());
+        let diagnostic = if cfg!(feature = "legacy-waker") {
+            quote! {}
+        } else {
             quote! {
-                #(#driver_attrs)*
-                let result: #control_flow_ty_use = #driver_fn_expr (#(#driver_fn_args,)* #for_body);
+                #[diagnostic::on_unimplemented(
+                    message = "`cbit!` iterators must return a `ControlFlow` but this one returns `{Self}`",
+                    label = "this call does not return a `ControlFlow`",
+                    note = "add an `unwrap(<adapter>)` clause after the call to convert its return value into a `ControlFlow`",
+                )]
+            }
+        };
+
+        // The value is bound first so that the error points at the call rather than at the entire
+        // invocation.
+        let driver_result = Ident::new("driver_result", call.span());
+        let into_control_flow = quote! {
+            let #driver_result = #driver_call;
+            #returns_control_flow::into_control_flow(#driver_result)
+        };
+
+        quote! {{
+            #diagnostic
+            trait #returns_control_flow<B, C> {
+                fn into_control_flow(self) -> #control_flow_<B, C>;
             }
+
+            impl<B, C> #returns_control_flow<B, C> for #control_flow_<B, C> {
+                #[inline(always)]
+                fn into_control_flow(self) -> #control_flow_<B, C> {
+                    self
+                }
+            }
+
+            #into_control_flow
+        }}
+    };
+
+    let (hoisted_args, driver_fn_args) = hoist_args(&input.call);
+    let driver_call = build_driver_call(&input.call, driver_fn_args);
+
+    // Each chained iterator only runs once the previous one completed, whose completion value is
+    // discarded. All of the attributes on a chained call apply to it alone so a `#[cfg(...)]` only
+    // skips that iterator.
+    let chained_calls = input.chain.iter().map(|then| {
+        let attrs = then.call.attrs();
+        let (hoisted_args, driver_fn_args) = hoist_args(&then.call);
+        let driver_call = build_driver_call(&then.call, driver_fn_args);
+
+        quote! {
+            #(#attrs)*
+            let result: #control_flow_ty_use = match result {
+                #control_flow_::Continue(_) => {
+                    #hoisted_args
+                    #driver_call
+                }
+                #control_flow_::Break(result) => #control_flow_::Break(result),
+            };
+        }
+    });
+
+    let driver_call_site = if options.dyn_callback {
+        quote! {
+            #hoisted_args
+            #first_flag_decl
+            let mut #dyn_break_payload = #option_::None;
+            #(#driver_attrs)*
+            let result: #control_flow_ty_use = match #driver_call {
+                #control_flow_::Break(()) => #control_flow_::Break(
+                    #dyn_break_payload.expect(
+                        "iterators driven with `#[dyn_callback]` must only break when their \
+                         callback does"
+                    ),
+                ),
+                #control_flow_::Continue(value) => #control_flow_::Continue(value),
+            };
+        }
+    } else {
+        quote! {
+            #hoisted_args
+            #first_flag_decl
+            #chained_body_decl
+            #(#driver_attrs)*
+            let result: #control_flow_ty_use = #driver_call;
+            #(#chained_calls)*
+        }
+    };
+
+    let trace_early_return = trace("EarlyReturn");
+    let trace_early_break = trace("EarlyBreak");
+    let trace_completion = trace("Completion");
+
+    // Mismatches between the type of `break` values and the value the iterator completes with are
+    // reported on the iterator call.
+    let completion_arm = if let Some(span) = options.infinite {
+        quote_spanned! {span=> match result {} }
+    } else {
+        let last_call = input.chain.last().map_or(&input.call, |then| &then.call);
+        let result = Ident::new("result", Span::call_site().located_at(last_call.span()));
+        quote! {{
+            #trace_completion
+            #result
+        }}
+    };
+
+    // The `on_break` hook sees the value before `try` mode unwraps it.
+    let early_break_result = match &options.on_break {
+        Some(hook) => quote! { (#hook)(result) },
+        None => quote! { result },
+    };
+    let early_break_result = match &input.kw_try {
+        Some(kw_try) => quote_spanned! {kw_try.span=> #early_break_result? },
+        None => early_break_result,
+    };
+
+    // Wrapping the final match in a labeled block keeps an undeclared clause label down to a
+    // single `E0426` at the user's label instead of a cascade of "`break` outside of a loop"
+    // errors pointing at the macro invocation. The match is often the last statement of an outer
+    // loop so `clippy::needless_continue` would flag our `continue`s as redundant.
+    let final_label = internal_label("final", Span::call_site());
+    let final_match = match (&input.raw, &input.raw_handler) {
+        (Some(raw), Some((_, handler))) => {
+            let ident = &raw.ident;
+            quote! {{
+                use OurControlFlowResult as Outcome;
+                let #ident = result;
+                #handler
+            }}
         }
-        syntax::AnyCallExpr::Method(call) => {
-            let driver_attrs = &call.attrs;
-            let driver_receiver_expr = &call.receiver;
-            let driver_method = &call.method;
-            let driver_turbo = &call.turbofish;
-            let driver_fn_args = call.args.iter();
+        _ => quote! {
+            #[allow(unused_labels, clippy::needless_continue)]
+            #final_label: {
+                match result {
+                    #control_flow_::Break(result) => match result {
+                        OurControlFlowResult::EarlyReturn(early_result) => {
+                            #trace_early_return
+                            return early_result
+                        }
+                        OurControlFlowResult::EarlyBreak(result) => {
+                            #trace_early_break
+                            #early_break_result
+                        }
+                        #(#break_out_matchers)*
+                        #(#continue_out_matchers)*
+                        #(#continue_with_out_matchers)*
+                    },
+                    #control_flow_::Continue(result) => #completion_arm
+                }
+            }
+        },
+    };
 
+    // A `-> <type>` annotation pins the type of the loop's value, whichever path produced it.
+    let final_match = match &input.result_ty {
+        Some((_, ty)) => {
+            let loop_result = Ident::new("loop_result", Span::mixed_site());
             quote! {
-                #(#driver_attrs)*
-                let result: #control_flow_ty_use =
-                    #driver_receiver_expr.#driver_method #driver_turbo (
-                        #(#driver_fn_args,)*
-                        #for_body
-                    );
+                let #loop_result: #ty = { #final_match };
+                #loop_result
             }
         }
+        None => final_match,
     };
 
     // Put it all together
-    quote! {{
+    let expansion = quote! {{
+        #unused_label_warnings
+
         // enum ControlFlowResult<...> { ... }
         #control_flow_enum_def
 
@@ -668,15 +4315,117 @@ pub fn cbit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         // let result = my_fn(args, |...| async { ... });
         #driver_call_site
 
-        match result {
-            #ops_::ControlFlow::Break(result) => match result {
-                OurControlFlowResult::EarlyReturn(early_result) => return early_result,
-                OurControlFlowResult::EarlyBreak(result) => result,
-                #(#break_out_matchers)*
-                #(#continue_out_matchers)*
-            },
-            #ops_::ControlFlow::Continue(result) => result,
+        #final_match
+    }};
+
+    if cfg_attrs.is_empty() {
+        Ok(expansion)
+    } else {
+        // A configurable loop can be removed entirely so it can only ever evaluate to `()`.
+        Ok(quote! {{
+            #(#cfg_attrs)*
+            let () = #expansion;
+        }})
+    }
+}
+
+/// The prefix of every label generated for our own absorber layers. These labels resolve at the
+/// call site so they are given names which users (and any macros wrapping ours) are unlikely to
+/// pick. Every internal label must be created through [`internal_label`] so that it can be
+/// recognized by its prefix.
+const INTERNAL_PREFIX: &str = "__cbit_absorber_magic_";
+
+fn internal_label(name: &str, span: Span) -> Lifetime {
+    Lifetime::new(&format!("'{INTERNAL_PREFIX}{name}"), span)
+}
+
+/// Converts a label's name to `UpperCamelCase` (e.g. `'outer_rows` to `OuterRows`) for use in the
+/// names of the control-flow enum's variants.
+fn camel_case_label(lt: &Lifetime) -> String {
+    let name = lt.ident.to_string();
+    let camel = name
+        .trim_start_matches("r#")
+        .split('_')
+        .flat_map(|segment| {
+            let mut chars = segment.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+        })
+        .collect::<String>();
+
+    // Labels consisting solely of underscores (e.g. `'__`) have no characters left.
+    if camel.is_empty() {
+        "Underscore".to_string()
+    } else {
+        camel
+    }
+}
+
+fn check_foreign_labels(
+    input: &CbitForExpr,
+    in_break_labels: &Punctuated<CbitForExprSingleBreak, Token![,]>,
+    info: &BodyInfo,
+) -> syn::Result<()> {
+    let is_own_label = |lt: &Lifetime| {
+        lt.ident.to_string().starts_with(INTERNAL_PREFIX)
+            || input
+                .label
+                .as_ref()
+                .is_some_and(|label| label.name.ident == lt.ident)
+    };
+
+    let find_decl = |lt: &Lifetime| in_break_labels.iter().find(|v| v.lt.ident == lt.ident);
+
+    let undeclared = |lt: &Lifetime, verb: &str, decl: String| {
+        let fix = if input.breaks.is_some() {
+            format!("add `{decl}` to its `break` clause")
+        } else {
+            format!("add `break {decl}` after the iterator call")
+        };
+
+        syn::Error::new(
+            lt.span(),
+            format!(
+                "cannot `{verb}` to `{lt}` because this `cbit!` loop does not declare it; {fix}"
+            ),
+        )
+    };
+
+    let mut errors = Vec::new();
+
+    for lt in &info.foreign_breaks {
+        if !is_own_label(lt) && find_decl(lt).is_none() {
+            errors.push(undeclared(lt, "break", lt.to_string()));
         }
-    }}
-    .into()
+    }
+
+    for lt in &info.foreign_continues {
+        if is_own_label(lt) {
+            continue;
+        }
+
+        match find_decl(lt) {
+            Some(decl) if decl.accepts_continue() => {}
+            Some(_) => errors.push(syn::Error::new(
+                lt.span(),
+                format!(
+                    "cannot `continue` to `{lt}` because this `cbit!` loop declares it as a block; \
+                     declare it as `loop {lt}` (or `while {lt}` or `for {lt}`) in the `break` clause \
+                     instead",
+                ),
+            )),
+            None => errors.push(undeclared(lt, "continue", format!("loop {lt}"))),
+        }
+    }
+
+    match errors.into_iter().reduce(|mut a, b| {
+        a.combine(b);
+        a
+    }) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
 }
@@ -0,0 +1,485 @@
+use proc_macro2::{Delimiter, Group, Ident, Spacing, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{parse::Parser, Expr, Lifetime, LitInt};
+
+/// Rewrites every `continue '<label> with <expr>` in a loop body into a `break '<internal> (<expr>)`
+/// where `targets` maps each user label to the internal label absorbing its values. Unlabeled
+/// `continue with <expr>` expressions always continue the virtual loop and break out to
+/// `virtual_target` instead.
+///
+/// This does not descend into macro invocations since their contents may not be Rust expressions (or
+/// may be other `cbit!` loops doing their own rewriting).
+pub fn rewrite_continue_with(
+    body: TokenStream,
+    targets: &[(&Lifetime, &Lifetime)],
+    virtual_target: &Lifetime,
+) -> syn::Result<TokenStream> {
+    let mut out = TokenStream::new();
+    let mut tokens = body.into_iter().collect::<Vec<_>>();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if let Some((internal, consumed)) =
+            match_continue_with(&tokens[i..], targets, virtual_target)
+        {
+            let tail = tokens[(i + consumed)..]
+                .iter()
+                .cloned()
+                .collect::<TokenStream>();
+            let (value, tail) = (|input: syn::parse::ParseStream| {
+                Ok((input.parse::<Expr>()?, input.parse::<TokenStream>()?))
+            })
+            .parse2(tail)
+            .map_err(|err| {
+                syn::Error::new(
+                    err.span(),
+                    "expected the value to `continue` with after the `with` keyword",
+                )
+            })?;
+
+            out.extend(quote! { break #internal (#value) });
+            tokens = tail.into_iter().collect();
+            i = 0;
+            continue;
+        }
+
+        match &tokens[i] {
+            TokenTree::Group(group) if !is_macro_invocation(&tokens[..i]) => {
+                let mut rewritten = Group::new(
+                    group.delimiter(),
+                    rewrite_continue_with(group.stream(), targets, virtual_target)?,
+                );
+                rewritten.set_span(group.span());
+                out.extend([TokenTree::Group(rewritten)]);
+            }
+            tt => out.extend([tt.clone()]),
+        }
+        i += 1;
+    }
+
+    Ok(out)
+}
+
+/// Matches a `continue with` expression at the start of `tokens`, returning the internal label it
+/// should break out to and the number of tokens preceding its value.
+fn match_continue_with<'a>(
+    tokens: &[TokenTree],
+    targets: &[(&Lifetime, &'a Lifetime)],
+    virtual_target: &'a Lifetime,
+) -> Option<(&'a Lifetime, usize)> {
+    if let [TokenTree::Ident(kw_continue), TokenTree::Ident(kw_with), ..] = tokens {
+        if kw_continue == "continue" && kw_with == "with" {
+            return Some((virtual_target, 2));
+        }
+    }
+
+    let [TokenTree::Ident(kw_continue), TokenTree::Punct(apostrophe), TokenTree::Ident(label), TokenTree::Ident(kw_with), ..] =
+        tokens
+    else {
+        return None;
+    };
+
+    if kw_continue != "continue"
+        || apostrophe.as_char() != '\''
+        || apostrophe.spacing() != Spacing::Joint
+        || kw_with != "with"
+    {
+        return None;
+    }
+
+    targets
+        .iter()
+        .find(|(user, _)| user.ident == *label)
+        .map(|(_, internal)| (*internal, 4))
+}
+
+/// Rewrites every `redo with <expr>` in a loop body into a `break '<target> (<expr>)`, returning the
+/// rewritten body and whether it contained any such expression.
+///
+/// Like [`rewrite_continue_with`], this does not descend into macro invocations.
+pub fn rewrite_redo_with(body: TokenStream, target: &Lifetime) -> syn::Result<(TokenStream, bool)> {
+    let mut out = TokenStream::new();
+    let mut tokens = body.into_iter().collect::<Vec<_>>();
+    let mut found = false;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if let [TokenTree::Ident(kw_redo), TokenTree::Ident(kw_with), ..] = &tokens[i..] {
+            if kw_redo == "redo" && kw_with == "with" {
+                let tail = tokens[(i + 2)..].iter().cloned().collect::<TokenStream>();
+                let (value, tail) = (|input: syn::parse::ParseStream| {
+                    Ok((input.parse::<Expr>()?, input.parse::<TokenStream>()?))
+                })
+                .parse2(tail)
+                .map_err(|err| {
+                    syn::Error::new(
+                        err.span(),
+                        "expected the value to `redo` the body with after the `with` keyword",
+                    )
+                })?;
+
+                out.extend(quote! { break #target (#value) });
+                found = true;
+                tokens = tail.into_iter().collect();
+                i = 0;
+                continue;
+            }
+        }
+
+        match &tokens[i] {
+            TokenTree::Group(group) if !is_macro_invocation(&tokens[..i]) => {
+                let (stream, found_inner) = rewrite_redo_with(group.stream(), target)?;
+                let mut rewritten = Group::new(group.delimiter(), stream);
+                rewritten.set_span(group.span());
+                out.extend([TokenTree::Group(rewritten)]);
+                found |= found_inner;
+            }
+            tt => out.extend([tt.clone()]),
+        }
+        i += 1;
+    }
+
+    Ok((out, found))
+}
+
+/// Determines whether a group following the `preceding` tokens holds the arguments of a macro
+/// invocation (e.g. `foo!(...)`) rather than some regular expression (e.g. `if !(...)`).
+pub fn is_macro_invocation(preceding: &[TokenTree]) -> bool {
+    let [.., TokenTree::Ident(name), TokenTree::Punct(bang)] = preceding else {
+        return false;
+    };
+
+    // `Ident`'s parser rejects keywords.
+    bang.as_char() == '!' && syn::parse2::<Ident>(name.to_token_stream()).is_ok()
+}
+
+/// Rewrites every `break out` in a loop body into a `break '<label>`, leaving any value following it
+/// in place.
+///
+/// Like [`rewrite_continue_with`], this does not descend into macro invocations. It also leaves
+/// native loops, closures, `async` and `const` blocks, and items alone since `break out` is valid
+/// Rust there when `out` is a variable.
+pub fn rewrite_break_out(body: TokenStream, label: &Lifetime) -> TokenStream {
+    let tokens = body.into_iter().collect::<Vec<_>>();
+    let mut out = TokenStream::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if let Some(end) =
+            opaque_construct_end(&tokens, i).or_else(|| native_loop_header_end(&tokens, i))
+        {
+            out.extend(tokens[i..end].iter().cloned());
+            i = end;
+            continue;
+        }
+
+        if let [TokenTree::Ident(kw_break), TokenTree::Ident(kw_out), ..] = &tokens[i..] {
+            if kw_break == "break" && kw_out == "out" {
+                out.extend([TokenTree::Ident(kw_break.clone())]);
+                label.to_tokens(&mut out);
+                i += 2;
+                continue;
+            }
+        }
+
+        match &tokens[i] {
+            TokenTree::Group(group) if !is_macro_invocation(&tokens[..i]) => {
+                let mut rewritten =
+                    Group::new(group.delimiter(), rewrite_break_out(group.stream(), label));
+                rewritten.set_span(group.span());
+                out.extend([TokenTree::Group(rewritten)]);
+            }
+            tt => out.extend([tt.clone()]),
+        }
+        i += 1;
+    }
+
+    out
+}
+
+/// Rewrites every `break <depth>` and `continue <depth>` in a loop body into a `break '<label>` or
+/// `continue '<label>` where `levels` lists the labels of the enclosing `cbit!` loops, outermost
+/// first, and ends with the label of the loop whose body this is.
+///
+/// Nested `cbit!` invocations are not descended into but are instead told about `levels` so that
+/// they can do their own rewriting. Like `scan::Scanner`, this leaves closures, `async` and `const`
+/// blocks, and items alone since their bodies can't break out to our labels. Native `loop`, `while`,
+/// and `for` loops are descended into without rewriting since a `break` or `continue` there refers
+/// to the native loop, so only `cbit!` loops nested within them are told about `levels`.
+pub fn rewrite_numbered_breaks(body: TokenStream, levels: &[Lifetime]) -> syn::Result<TokenStream> {
+    rewrite_numbered_breaks_in(body, levels, true)
+}
+
+fn rewrite_numbered_breaks_in(
+    body: TokenStream,
+    levels: &[Lifetime],
+    rewrite: bool,
+) -> syn::Result<TokenStream> {
+    let tokens = body.into_iter().collect::<Vec<_>>();
+    let mut out = TokenStream::new();
+    let mut native_loop_end = 0;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if let Some(end) = opaque_construct_end(&tokens, i) {
+            out.extend(tokens[i..end].iter().cloned());
+            i = end;
+            continue;
+        }
+
+        if i >= native_loop_end {
+            if let Some(end) = native_loop_header_end(&tokens, i) {
+                native_loop_end = end;
+            }
+        }
+
+        let rewrite_here = rewrite && i >= native_loop_end;
+        let tt = &tokens[i];
+
+        if let (true, Some(TokenTree::Ident(kw)), TokenTree::Literal(lit)) =
+            (rewrite_here, i.checked_sub(1).map(|i| &tokens[i]), tt)
+        {
+            if let (true, Ok(lit)) = (
+                kw == "break" || kw == "continue",
+                syn::parse2::<LitInt>(lit.to_token_stream()),
+            ) {
+                let depth = lit.base10_parse::<usize>()?;
+                if depth == 0 || depth > levels.len() {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        format!(
+                            "cannot `{kw} {depth}` because there {}; depths start at 1 for the \
+                             innermost `cbit!` loop",
+                            match levels.len() {
+                                1 => "is only 1 enclosing `cbit!` loop".to_string(),
+                                n => format!("are only {n} enclosing `cbit!` loops"),
+                            },
+                        ),
+                    ));
+                }
+
+                levels[levels.len() - depth].to_tokens(&mut out);
+                i += 1;
+                continue;
+            }
+        }
+
+        match tt {
+            TokenTree::Group(group) if is_cbit_invocation(&tokens[..i]) => {
+                let mut rewritten = Group::new(group.delimiter(), {
+                    let group_stream = group.stream();
+                    quote! {
+                        #[numbered_breaks(#(#levels),*)]
+                        #group_stream
+                    }
+                });
+                rewritten.set_span(group.span());
+                out.extend([TokenTree::Group(rewritten)]);
+            }
+            TokenTree::Group(group) if !is_macro_invocation(&tokens[..i]) => {
+                let mut rewritten = Group::new(
+                    group.delimiter(),
+                    rewrite_numbered_breaks_in(group.stream(), levels, rewrite_here)?,
+                );
+                rewritten.set_span(group.span());
+                out.extend([TokenTree::Group(rewritten)]);
+            }
+            tt => out.extend([tt.clone()]),
+        }
+        i += 1;
+    }
+
+    Ok(out)
+}
+
+/// If a native `loop`, `while`, or `for` loop starts at `tokens[i]`, returns the index just past its
+/// body. The `while` condition and `for` iterator are included since a `break` there is either an
+/// error or refers to the native loop too.
+fn native_loop_header_end(tokens: &[TokenTree], i: usize) -> Option<usize> {
+    let TokenTree::Ident(kw) = &tokens[i] else {
+        return None;
+    };
+
+    if kw == "loop" {
+        return is_brace(tokens.get(i + 1)?).then_some(i + 2);
+    }
+
+    // `for<'a>` is a higher-ranked binder in a type rather than a loop.
+    let is_for_loop = kw == "for"
+        && !matches!(tokens.get(i + 1), Some(TokenTree::Punct(p)) if p.as_char() == '<');
+
+    if kw == "while" || is_for_loop {
+        // Struct literals aren't allowed in the condition or iterator so the first brace after the
+        // pattern, if any, is the body.
+        let header = &tokens[(i + 1)..];
+        let pattern_end = if is_for_loop {
+            header
+                .iter()
+                .position(|tt| matches!(tt, TokenTree::Ident(kw) if kw == "in"))?
+        } else if matches!(header.first(), Some(TokenTree::Ident(kw)) if kw == "let") {
+            // The `=` ending the pattern, as opposed to the tail of `==`, `<=`, `..=`, etc.
+            header.iter().enumerate().position(|(j, tt)| {
+                matches!(tt, TokenTree::Punct(p) if p.as_char() == '=' && p.spacing() == Spacing::Alone)
+                    && !matches!(
+                        j.checked_sub(1).map(|j| &header[j]),
+                        Some(TokenTree::Punct(prev)) if prev.spacing() == Spacing::Joint
+                    )
+            })?
+        } else {
+            0
+        };
+        let body = header[pattern_end..].iter().position(is_brace)?;
+        return Some(i + 1 + pattern_end + body + 1);
+    }
+
+    None
+}
+
+/// If a closure, `async` or `const` block, or item starts at `tokens[i]`, returns the index just past
+/// its end.
+fn opaque_construct_end(tokens: &[TokenTree], i: usize) -> Option<usize> {
+    match &tokens[i] {
+        // `async { ... }` and `async move { ... }`
+        TokenTree::Ident(kw) if kw == "async" => {
+            let body = match tokens.get(i + 1)? {
+                TokenTree::Ident(kw_move) if kw_move == "move" => i + 2,
+                _ => i + 1,
+            };
+            is_brace(tokens.get(body)?).then_some(body + 1)
+        }
+
+        // `const { ... }`
+        TokenTree::Ident(kw) if kw == "const" && tokens.get(i + 1).is_some_and(is_brace) => {
+            Some(i + 2)
+        }
+
+        // Items, which end at their first top-level `;` or brace.
+        TokenTree::Ident(kw) if is_item_keyword(kw) && is_item_start(&tokens[..i]) => {
+            let end = tokens[i..].iter().position(|tt| {
+                is_brace(tt) || matches!(tt, TokenTree::Punct(p) if p.as_char() == ';')
+            })?;
+            Some(i + end + 1)
+        }
+
+        // Closures, which end with their body.
+        TokenTree::Punct(p) if p.as_char() == '|' && is_expr_start(&tokens[..i]) => {
+            let params_end = if p.spacing() == Spacing::Joint
+                && matches!(tokens.get(i + 1), Some(TokenTree::Punct(p)) if p.as_char() == '|')
+            {
+                i + 2
+            } else {
+                i + 2
+                    + tokens[(i + 1)..]
+                        .iter()
+                        .position(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == '|'))?
+            };
+
+            // A closure with a return type must have a block body. Otherwise, the body is a single
+            // expression running up to the next top-level `,` or `;`.
+            let body_end = tokens[params_end..]
+                .iter()
+                .position(|tt| {
+                    matches!(tt, TokenTree::Punct(p) if p.as_char() == ',' || p.as_char() == ';')
+                })
+                .map_or(tokens.len(), |end| params_end + end);
+
+            match tokens[params_end..body_end].iter().position(is_brace) {
+                Some(brace) if brace == 0 || is_return_type_arrow(&tokens[params_end..]) => {
+                    Some(params_end + brace + 1)
+                }
+                _ => Some(body_end),
+            }
+        }
+
+        _ => None,
+    }
+}
+
+fn is_brace(tt: &TokenTree) -> bool {
+    matches!(tt, TokenTree::Group(group) if group.delimiter() == Delimiter::Brace)
+}
+
+fn is_item_keyword(kw: &Ident) -> bool {
+    [
+        "fn",
+        "struct",
+        "enum",
+        "union",
+        "impl",
+        "trait",
+        "mod",
+        "static",
+        "const",
+        "type",
+        "macro_rules",
+    ]
+    .iter()
+    .any(|item| kw == item)
+}
+
+/// Determines whether an item keyword following the `preceding` tokens starts an item rather than
+/// appearing within a type (e.g. `fn()` pointers or `*const T`).
+fn is_item_start(mut preceding: &[TokenTree]) -> bool {
+    // Skip over attributes and qualifiers such as `pub(crate) unsafe extern "C"`.
+    loop {
+        preceding = match preceding {
+            [rest @ .., TokenTree::Ident(kw)]
+                if ["pub", "unsafe", "async", "extern", "const", "default"]
+                    .iter()
+                    .any(|qualifier| kw == qualifier) =>
+            {
+                rest
+            }
+            [rest @ .., TokenTree::Literal(_)] => rest,
+            [rest @ .., TokenTree::Ident(kw), TokenTree::Group(group)]
+                if kw == "pub" && group.delimiter() == Delimiter::Parenthesis =>
+            {
+                rest
+            }
+            [rest @ .., TokenTree::Punct(pound), TokenTree::Group(group)]
+                if pound.as_char() == '#' && group.delimiter() == Delimiter::Bracket =>
+            {
+                rest
+            }
+            _ => break,
+        };
+    }
+
+    match preceding {
+        [] => true,
+        [.., TokenTree::Punct(p)] => p.as_char() == ';',
+        [.., tt] => is_brace(tt),
+    }
+}
+
+/// Determines whether a `|` following the `preceding` tokens starts a closure rather than being a
+/// binary or.
+fn is_expr_start(preceding: &[TokenTree]) -> bool {
+    match preceding {
+        [] => true,
+        [.., TokenTree::Punct(p)] => p.as_char() != '?',
+        [.., TokenTree::Ident(kw)] => ["move", "async", "return", "break", "in", "yield"]
+            .iter()
+            .any(|keyword| kw == keyword),
+        _ => false,
+    }
+}
+
+/// Determines whether the tokens following a closure's parameters start with a `->` return type.
+fn is_return_type_arrow(tokens: &[TokenTree]) -> bool {
+    matches!(
+        tokens,
+        [TokenTree::Punct(minus), TokenTree::Punct(gt), ..]
+            if minus.as_char() == '-' && minus.spacing() == Spacing::Joint && gt.as_char() == '>'
+    )
+}
+
+/// Determines whether a group following the `preceding` tokens holds the input of a nested `cbit!`
+/// loop.
+fn is_cbit_invocation(preceding: &[TokenTree]) -> bool {
+    matches!(
+        preceding,
+        [.., TokenTree::Ident(name), TokenTree::Punct(bang)]
+            if name == "cbit" && bang.as_char() == '!'
+    )
+}
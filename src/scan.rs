@@ -0,0 +1,260 @@
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use syn::{
+    parse::Parser,
+    visit::{self, Visit},
+    Block, ExprAsync, ExprBlock, ExprBreak, ExprClosure, ExprConst, ExprContinue, ExprForLoop,
+    ExprLoop, ExprWhile, Item, Label, Lifetime, Macro,
+};
+
+use crate::rewrite::is_macro_invocation;
+
+/// Facts about a loop body gathered by [`scan_body`].
+#[derive(Default)]
+pub struct BodyInfo {
+    /// Labels targeted by `break` expressions which are not declared within the body.
+    pub foreign_breaks: Vec<Lifetime>,
+
+    /// Labels targeted by `continue` expressions which are not declared within the body.
+    pub foreign_continues: Vec<Lifetime>,
+
+    /// Whether the body may contain an unlabeled `continue` of the virtual loop. Macro invocations
+    /// mentioning `continue` are assumed to do so.
+    pub may_continue_unlabeled: bool,
+
+    /// Whether a macro invocation in the body mentions `continue`, in which case it may continue
+    /// any label.
+    pub may_continue_opaquely: bool,
+
+    /// Labels passed to macro invocations, which may expand to a `continue` of them.
+    pub opaque_labels: Vec<Lifetime>,
+}
+
+impl BodyInfo {
+    /// Whether the body may `continue` the given external label.
+    pub fn may_continue(&self, lt: &Lifetime) -> bool {
+        self.may_continue_opaquely
+            || self
+                .foreign_continues
+                .iter()
+                .chain(&self.opaque_labels)
+                .any(|v| v.ident == lt.ident)
+    }
+}
+
+/// Scans the body of a `cbit!` loop. Returns `None` if the body is not valid Rust, in which case
+/// we leave the error reporting to `rustc`.
+pub fn scan_body(body: &TokenStream) -> Option<BodyInfo> {
+    let stmts = Block::parse_within.parse2(body.clone()).ok()?;
+
+    let mut scanner = Scanner::default();
+    for stmt in &stmts {
+        scanner.visit_stmt(stmt);
+    }
+
+    Some(scanner.info)
+}
+
+#[derive(Default)]
+struct Scanner {
+    labels_in_scope: Vec<Lifetime>,
+    loop_depth: usize,
+    info: BodyInfo,
+}
+
+impl Scanner {
+    fn with_label(&mut self, label: Option<&Label>, f: impl FnOnce(&mut Self)) {
+        let Some(label) = label else {
+            f(self);
+            return;
+        };
+
+        self.labels_in_scope.push(label.name.clone());
+        f(self);
+        self.labels_in_scope.pop();
+    }
+
+    fn within_loop(&mut self, f: impl FnOnce(&mut Self)) {
+        self.loop_depth += 1;
+        f(self);
+        self.loop_depth -= 1;
+    }
+
+    fn is_foreign(&self, lt: &Lifetime) -> bool {
+        !self.labels_in_scope.iter().any(|v| v.ident == lt.ident)
+    }
+}
+
+impl<'ast> Visit<'ast> for Scanner {
+    // Labels cannot cross closure, `async`, `const`, and item boundaries so any control flow within
+    // them cannot target the loop and is left for `rustc` to validate. Macro invocations are never
+    // descended into since their contents are opaque to us.
+    fn visit_expr_closure(&mut self, _i: &'ast ExprClosure) {}
+
+    fn visit_expr_async(&mut self, _i: &'ast ExprAsync) {}
+
+    fn visit_expr_const(&mut self, _i: &'ast ExprConst) {}
+
+    fn visit_item(&mut self, _i: &'ast Item) {}
+
+    fn visit_expr_block(&mut self, i: &'ast ExprBlock) {
+        self.with_label(i.label.as_ref(), |this| visit::visit_expr_block(this, i));
+    }
+
+    fn visit_expr_loop(&mut self, i: &'ast ExprLoop) {
+        self.with_label(i.label.as_ref(), |this| {
+            this.within_loop(|this| visit::visit_expr_loop(this, i))
+        });
+    }
+
+    fn visit_expr_while(&mut self, i: &'ast ExprWhile) {
+        // The condition is evaluated outside of the loop's body.
+        visit::visit_expr(self, &i.cond);
+        self.with_label(i.label.as_ref(), |this| {
+            this.within_loop(|this| visit::visit_block(this, &i.body))
+        });
+    }
+
+    fn visit_expr_for_loop(&mut self, i: &'ast ExprForLoop) {
+        // The iterator is evaluated outside of the loop's body.
+        visit::visit_expr(self, &i.expr);
+        self.with_label(i.label.as_ref(), |this| {
+            this.within_loop(|this| visit::visit_block(this, &i.body))
+        });
+    }
+
+    fn visit_macro(&mut self, i: &'ast Macro) {
+        if mentions_continue(i.tokens.clone()) {
+            self.info.may_continue_unlabeled = true;
+            self.info.may_continue_opaquely = true;
+        }
+        collect_lifetimes(i.tokens.clone(), &mut self.info.opaque_labels);
+    }
+
+    fn visit_expr_break(&mut self, i: &'ast ExprBreak) {
+        if let Some(lt) = &i.label {
+            if self.is_foreign(lt) {
+                self.info.foreign_breaks.push(lt.clone());
+            }
+        }
+        visit::visit_expr_break(self, i);
+    }
+
+    fn visit_expr_continue(&mut self, i: &'ast ExprContinue) {
+        match &i.label {
+            Some(lt) if self.is_foreign(lt) => self.info.foreign_continues.push(lt.clone()),
+            Some(_) => {}
+            None if self.loop_depth == 0 => self.info.may_continue_unlabeled = true,
+            None => {}
+        }
+        visit::visit_expr_continue(self, i);
+    }
+}
+
+/// Rejects `continue` expressions followed by a value (e.g. `continue 'x value`), which Rust does
+/// not support. This runs on the token level since such bodies are not valid Rust and must be
+/// checked before `continue 'x with <value>` expressions for labels without a target are passed on
+/// to `rustc`, which would otherwise produce a generic parse error.
+///
+/// Macro invocations are not descended into.
+pub fn check_continue_values(body: TokenStream) -> syn::Result<()> {
+    let tokens = body.into_iter().collect::<Vec<_>>();
+    let mut errors = Vec::<syn::Error>::new();
+
+    for (i, tt) in tokens.iter().enumerate() {
+        match tt {
+            TokenTree::Ident(kw) if kw == "continue" => {
+                let rest = &tokens[(i + 1)..];
+                let (label, rest) = match rest {
+                    [TokenTree::Punct(apostrophe), TokenTree::Ident(label), rest @ ..]
+                        if apostrophe.as_char() == '\''
+                            && apostrophe.spacing() == Spacing::Joint =>
+                    {
+                        (Some(label), rest)
+                    }
+                    _ => (None, rest),
+                };
+
+                let Some(value) = rest.first().filter(|tt| begins_value(tt)) else {
+                    continue;
+                };
+
+                let message = match (label, value) {
+                    (Some(label), TokenTree::Ident(kw_with)) if kw_with == "with" => format!(
+                        "cannot `continue '{label}` with a value because this `cbit!` loop does not \
+                         give it a target; declare it as `loop '{label} with <target>` in the \
+                         `break` clause"
+                    ),
+                    (Some(label), _) => format!(
+                        "`continue` cannot carry a value; to continue `'{label}` with a value, \
+                         declare it as `loop '{label} with <target>` in the `break` clause and use \
+                         `continue '{label} with <value>`"
+                    ),
+                    (None, _) => "`continue` cannot carry a value; to hand a value back to the \
+                                  iterator of a `cbit!` loop, use `continue with <value>` or end \
+                                  its body with the value instead"
+                        .to_string(),
+                };
+
+                errors.push(syn::Error::new(value.span(), message));
+            }
+            TokenTree::Group(group) if !is_macro_invocation(&tokens[..i]) => {
+                if let Err(err) = check_continue_values(group.stream()) {
+                    errors.push(err);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    errors
+        .into_iter()
+        .reduce(|mut acc, err| {
+            acc.combine(err);
+            acc
+        })
+        .map_or(Ok(()), Err)
+}
+
+fn mentions_continue(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(ident) => ident == "continue",
+        TokenTree::Group(group) => mentions_continue(group.stream()),
+        _ => false,
+    })
+}
+
+/// Collects every label (or lifetime) mentioned anywhere in the tokens, including within macro
+/// invocations.
+pub fn mentioned_lifetimes(tokens: TokenStream) -> Vec<Lifetime> {
+    let mut out = Vec::new();
+    collect_lifetimes(tokens, &mut out);
+    out
+}
+
+fn collect_lifetimes(tokens: TokenStream, out: &mut Vec<Lifetime>) {
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    for (i, tt) in tokens.iter().enumerate() {
+        match (tt, tokens.get(i + 1)) {
+            (TokenTree::Punct(apostrophe), Some(TokenTree::Ident(ident)))
+                if apostrophe.as_char() == '\'' && apostrophe.spacing() == Spacing::Joint =>
+            {
+                out.push(Lifetime {
+                    apostrophe: apostrophe.span(),
+                    ident: ident.clone(),
+                });
+            }
+            (TokenTree::Group(group), _) => collect_lifetimes(group.stream(), out),
+            _ => {}
+        }
+    }
+}
+
+/// Determines whether a token following a `continue` expression can only be the start of a value.
+fn begins_value(tt: &TokenTree) -> bool {
+    match tt {
+        TokenTree::Literal(_) => true,
+        TokenTree::Ident(ident) => ident != "else" && ident != "as",
+        TokenTree::Group(group) => group.delimiter() != Delimiter::None,
+        TokenTree::Punct(_) => false,
+    }
+}
@@ -1,39 +1,646 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Delimiter, Ident, Spacing, Span, TokenStream, TokenTree};
+use quote::ToTokens;
 use syn::{
-    braced,
-    parse::{Parse, ParseStream},
+    braced, parenthesized,
+    parse::{discouraged::Speculative, Parse, ParseStream, Parser},
     punctuated::Punctuated,
-    token::Brace,
-    Expr, ExprCall, ExprMethodCall, Label, Lifetime, Pat, Token,
+    spanned::Spanned,
+    token::{Brace, Paren},
+    visit_mut::VisitMut,
+    Attribute, Block, Expr, ExprCall, ExprClosure, ExprMethodCall, Label, Lifetime, Macro,
+    MacroDelimiter, Meta, Pat, PatWild, ReturnType, Token, Type,
 };
 
 #[derive(Clone)]
 pub struct CbitForExpr {
+    pub attrs: Vec<Attribute>,
+    pub raw: Option<CbitRaw>,
+    pub elem_turbofish: Option<CbitElemTurbofish>,
+    pub kw_try: Option<Token![try]>,
     pub label: Option<Label>,
+    #[allow(dead_code)]
+    pub kw_do: Option<Token![do]>,
+    #[allow(dead_code)]
     pub kw_for: Token![for],
-    pub body_pattern: Pat,
+    pub binder: Option<CbitLifetimeBinder>,
+    pub binding: CbitBinding,
+    pub binding_ty: Option<(Token![:], Type)>,
+    pub first: Option<CbitFirstFlag>,
+    #[allow(dead_code)]
     pub kw_in: Token![in],
     pub call: AnyCallExpr,
+    pub chain: Vec<CbitThen>,
+    pub unwrap: Option<CbitUnwrap>,
+    pub result_ty: Option<(Token![->], Type)>,
     pub breaks: Option<CbitForExprBreaks>,
     pub body: OpaqueBody,
+    pub do_while: Option<(Token![while], Expr)>,
+    pub raw_handler: Option<(Token![;], Expr)>,
+}
+
+impl CbitForExpr {
+    /// The type of the elements passed to the body, if the user specified it.
+    pub fn elem_ty(&self) -> Option<&Type> {
+        self.binding_ty
+            .as_ref()
+            .map(|(_, ty)| ty)
+            .or(self.elem_turbofish.as_ref().map(|v| &v.ty))
+    }
+
+    /// The element type as it is written on the body closure's argument. Closures can't declare
+    /// lifetime parameters on stable Rust so the lifetimes of a `for<...>` binder are elided
+    /// instead, which makes the closure generic over them all the same.
+    pub fn closure_elem_ty(&self) -> Option<Type> {
+        let mut ty = self.elem_ty()?.clone();
+        if let Some(binder) = &self.binder {
+            binder.erase_in(&mut ty);
+        }
+        Some(ty)
+    }
 }
 
 impl Parse for CbitForExpr {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let raw = if input.peek(kw::raw) && input.peek2(syn::Ident) && input.peek3(Token![=>]) {
+            Some(input.parse::<CbitRaw>()?)
+        } else {
+            None
+        };
+        let elem_turbofish = if input.peek(Token![::]) {
+            Some(input.parse::<CbitElemTurbofish>()?)
+        } else {
+            None
+        };
+        let kw_try = if input.peek(Token![try]) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let label = input.parse()?;
+        let kw_do = if input.peek(Token![do]) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let kw_for = input.parse::<Token![for]>()?;
+        let binder = if input.peek(Token![<]) {
+            Some(input.parse::<CbitLifetimeBinder>()?)
+        } else {
+            None
+        };
+
+        // An omitted binding is shorthand for `_`.
+        let binding = if input.peek(Token![in]) {
+            CbitBinding::Pattern(Pat::Wild(PatWild {
+                attrs: Vec::new(),
+                underscore_token: Token![_](kw_for.span),
+            }))
+        } else {
+            input.parse()?
+        };
+
+        let binding_ty = if input.peek(Token![:]) {
+            let colon = input.parse::<Token![:]>()?;
+            if elem_turbofish.is_some() {
+                return Err(syn::Error::new(
+                    colon.span,
+                    "the element type was already specified by the leading `::<...>`",
+                ));
+            }
+            Some((colon, input.parse()?))
+        } else {
+            None
+        };
+
+        if let Some(binder) = &binder {
+            match binding_ty
+                .as_ref()
+                .map(|(_, ty)| ty)
+                .or(elem_turbofish.as_ref().map(|v| &v.ty))
+            {
+                Some(ty) => binder.validate(ty)?,
+                None => {
+                    return Err(syn::Error::new(
+                        binder.lt.span,
+                        "a `for<...>` binder requires an element type using its lifetimes",
+                    ))
+                }
+            }
+        }
+
+        let first = if input.peek(kw::first) && input.peek2(Paren) {
+            Some(input.parse::<CbitFirstFlag>()?)
+        } else {
+            None
+        };
+
+        let kw_in = input.parse()?;
+
+        // The iterator call is parsed as an expression, which would take the `-` of a following
+        // `-> <type>` for a subtraction, so the tokens before it are parsed on their own.
+        let (call, chain, unwrap) = match tokens_before_result_arrow(input) {
+            Some(len) => {
+                let mut tokens = TokenStream::new();
+                for _ in 0..len {
+                    tokens.extend([input.parse::<TokenTree>()?]);
+                }
+                parse_iterators.parse2(tokens)?
+            }
+            None => parse_iterators(input)?,
+        };
+        let result_ty = if input.peek(Token![->]) {
+            Some((input.parse()?, input.parse::<Type>()?))
+        } else {
+            None
+        };
+        let breaks = CbitForExprBreaks::parse(input)?;
+
+        // At the end of the input, `syn` would report the error on the entire invocation so we
+        // point it at whatever the body should have followed instead.
+        if input.is_empty() {
+            let end_span = match (&breaks, &result_ty, &unwrap, &call) {
+                (Some(breaks), _, _, _) => {
+                    let last = breaks.lt.last().unwrap();
+                    match &last.with {
+                        Some((_, target)) => target.span(),
+                        None => last.lt.span(),
+                    }
+                }
+                (None, Some((_, ty)), _, _) => ty.span(),
+                (None, None, Some(unwrap), _) => unwrap.paren.span.close(),
+                (None, None, None, call) => match chain.last().map_or(call, |then| &then.call) {
+                    AnyCallExpr::Function(call) => call.paren_token.span.close(),
+                    AnyCallExpr::Method(call) => call.paren_token.span.close(),
+                },
+            };
+
+            return Err(syn::Error::new(end_span, MISSING_BODY_MESSAGE));
+        }
+
+        let body = input.parse::<OpaqueBody>()?;
+
+        // Like the handler below, the condition follows the body so it can't follow a `=>` body.
+        let do_while = match (&kw_do, &body.delim) {
+            (Some(_), OpaqueBodyDelim::Arrow(arrow)) => {
+                return Err(syn::Error::new(
+                    arrow.spans[0],
+                    "a `do` loop requires a `{ ... }` body followed by `while <condition>`",
+                ))
+            }
+            (Some(_), delim) => {
+                if !input.peek(Token![while]) {
+                    return Err(syn::Error::new(
+                        delim.close_span(),
+                        "a `do` loop requires a `while <condition>` after its body",
+                    ));
+                }
+                Some((input.parse()?, input.parse()?))
+            }
+            (None, _) => None,
+        };
+
+        // The handler follows the body so a `=>` body, which extends to the end of the input, would
+        // swallow it.
+        let raw_handler = match (&raw, &body.delim) {
+            (Some(raw), OpaqueBodyDelim::Arrow(arrow)) => {
+                return Err(syn::Error::new(
+                    arrow.spans[0],
+                    format!(
+                        "a `raw` loop requires a `{{ ... }}` body followed by `;` and the \
+                         expression handling `{}`",
+                        raw.ident
+                    ),
+                ))
+            }
+            (Some(_), _) => Some((input.parse()?, input.parse()?)),
+            (None, _) => None,
+        };
+
         Ok(Self {
-            label: input.parse()?,
-            kw_for: input.parse()?,
-            body_pattern: Pat::parse_single(input)?,
-            kw_in: input.parse()?,
+            attrs,
+            raw,
+            elem_turbofish,
+            kw_try,
+            label,
+            kw_do,
+            kw_for,
+            binder,
+            binding,
+            binding_ty,
+            first,
+            kw_in,
+            call,
+            chain,
+            unwrap,
+            result_ty,
+            breaks,
+            body,
+            do_while,
+            raw_handler,
+        })
+    }
+}
+
+/// Parses the iterator call along with any chained calls and the `unwrap(...)` clause.
+fn parse_iterators(
+    input: ParseStream,
+) -> syn::Result<(AnyCallExpr, Vec<CbitThen>, Option<CbitUnwrap>)> {
+    let call = input.parse::<AnyCallExpr>()?;
+    let mut chain = Vec::new();
+    while input.peek(kw::then) {
+        chain.push(input.parse::<CbitThen>()?);
+    }
+    let unwrap = if input.peek(kw::unwrap) && input.peek2(Paren) {
+        let unwrap = input.parse::<CbitUnwrap>()?;
+        if !chain.is_empty() {
+            return Err(syn::Error::new(
+                unwrap.kw_unwrap.span,
+                "`unwrap(...)` cannot be used with iterators chained with `then`; each of them must \
+                 return a `ControlFlow` itself",
+            ));
+        }
+        Some(unwrap)
+    } else {
+        None
+    };
+    Ok((call, chain, unwrap))
+}
+
+/// Finds the number of tokens preceding the `->` of a result type annotation. `->`s within the
+/// angle brackets of generic arguments (e.g. in `f::<fn() -> u8>()`) are skipped and the search
+/// ends at the `break` clause or at the body, so that a `->` in a `raw` handler or `do ... while`
+/// condition following the body is never taken for the annotation.
+fn tokens_before_result_arrow(input: ParseStream) -> Option<usize> {
+    let mut cursor = input.cursor();
+    let mut depth = 0usize;
+    let mut len = 0;
+
+    while let Some((tt, next)) = cursor.token_tree() {
+        match &tt {
+            TokenTree::Ident(ident) if ident == "break" => return None,
+            // A `{ ... }` body, as opposed to a block receiving a method call, or an `@body` one.
+            TokenTree::Group(group)
+                if depth == 0
+                    && group.delimiter() == Delimiter::Brace
+                    && !next.punct().is_some_and(|(dot, _)| dot.as_char() == '.') =>
+            {
+                return None
+            }
+            TokenTree::Punct(punct) if depth == 0 && punct.as_char() == '@' => return None,
+            TokenTree::Punct(punct) if punct.spacing() == Spacing::Joint => {
+                let after = next.punct().filter(|(gt, _)| gt.as_char() == '>');
+                match (punct.as_char(), after) {
+                    ('=', Some(_)) => return None,
+                    ('-', Some(_)) if depth == 0 => return Some(len),
+                    ('-', Some((_, after))) => {
+                        cursor = after;
+                        len += 2;
+                        continue;
+                    }
+                    ('<', _) => depth += 1,
+                    ('>', _) => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '<' => depth += 1,
+            TokenTree::Punct(punct) if punct.as_char() == '>' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+
+        cursor = next;
+        len += 1;
+    }
+
+    None
+}
+
+/// A leading `raw <ident> =>` binding the unmatched result of the loop for a handler following its
+/// body.
+#[derive(Clone)]
+pub struct CbitRaw {
+    pub kw_raw: kw::raw,
+    pub ident: Ident,
+    #[allow(dead_code)]
+    pub arrow: Token![=>],
+}
+
+impl Parse for CbitRaw {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            kw_raw: input.parse()?,
+            ident: input.parse()?,
+            arrow: input.parse()?,
+        })
+    }
+}
+
+/// A leading `::<Type>` pinning the type of the elements passed to the body.
+#[derive(Clone)]
+pub struct CbitElemTurbofish {
+    #[allow(dead_code)]
+    pub colon2: Token![::],
+    #[allow(dead_code)]
+    pub lt: Token![<],
+    pub ty: Type,
+    #[allow(dead_code)]
+    pub gt: Token![>],
+}
+
+impl Parse for CbitElemTurbofish {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            colon2: input.parse()?,
+            lt: input.parse()?,
+            ty: input.parse()?,
+            gt: input.parse()?,
+        })
+    }
+}
+
+/// A `<'a, ...>` binder after the `for` keyword naming the lifetimes of the elements passed to the
+/// body.
+#[derive(Clone)]
+pub struct CbitLifetimeBinder {
+    pub lt: Token![<],
+    pub lifetimes: Punctuated<Lifetime, Token![,]>,
+    #[allow(dead_code)]
+    pub gt: Token![>],
+}
+
+impl Parse for CbitLifetimeBinder {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lt = input.parse()?;
+        let mut lifetimes = Punctuated::new();
+        while !input.peek(Token![>]) {
+            lifetimes.push_value(input.parse()?);
+            if input.peek(Token![>]) {
+                break;
+            }
+            lifetimes.push_punct(input.parse()?);
+        }
+
+        Ok(Self {
+            lt,
+            lifetimes,
+            gt: input.parse()?,
+        })
+    }
+}
+
+impl CbitLifetimeBinder {
+    /// Ensures that each of the binder's lifetimes appears at most once in `ty`. Every elided
+    /// lifetime of a closure argument is distinct so a lifetime used twice couldn't be honored.
+    fn validate(&self, ty: &Type) -> syn::Result<()> {
+        match self.erase_in(&mut ty.clone()) {
+            Some(lt) => Err(syn::Error::new(
+                lt.span(),
+                format!(
+                    "`{lt}` is used more than once in the element type but each lifetime of a \
+                     `for<...>` binder can only be used once; the body closure cannot require \
+                     two borrows to share a lifetime"
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Replaces the binder's lifetimes in `ty` with `'_`, returning the first one used twice.
+    fn erase_in(&self, ty: &mut Type) -> Option<Lifetime> {
+        let mut eraser = LifetimeEraser {
+            binder: self,
+            seen: Vec::new(),
+            reused: None,
+        };
+        eraser.visit_type_mut(ty);
+        eraser.reused
+    }
+}
+
+/// The visitor behind [`CbitLifetimeBinder::erase_in`].
+struct LifetimeEraser<'a> {
+    binder: &'a CbitLifetimeBinder,
+    seen: Vec<Lifetime>,
+    reused: Option<Lifetime>,
+}
+
+impl VisitMut for LifetimeEraser<'_> {
+    fn visit_lifetime_mut(&mut self, lt: &mut Lifetime) {
+        if !self
+            .binder
+            .lifetimes
+            .iter()
+            .any(|bound| bound.ident == lt.ident)
+        {
+            return;
+        }
+
+        if self.seen.iter().any(|seen| seen.ident == lt.ident) {
+            self.reused.get_or_insert_with(|| lt.clone());
+        } else {
+            self.seen.push(lt.clone());
+        }
+        *lt = Lifetime::new("'_", lt.span());
+    }
+}
+
+/// A `first(<ident>)` clause binding a `bool` which is only `true` for the first run of the body.
+#[derive(Clone)]
+pub struct CbitFirstFlag {
+    #[allow(dead_code)]
+    pub kw_first: kw::first,
+    #[allow(dead_code)]
+    pub paren: Paren,
+    pub ident: Ident,
+}
+
+impl Parse for CbitFirstFlag {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        Ok(Self {
+            kw_first: input.parse()?,
+            paren: parenthesized!(content in input),
+            ident: content.parse()?,
+        })
+    }
+}
+
+/// An `unwrap(<adapter>)` clause normalizing the value returned by the iterator into a
+/// `ControlFlow`.
+#[derive(Clone)]
+pub struct CbitUnwrap {
+    pub kw_unwrap: kw::unwrap,
+    pub paren: Paren,
+    pub adapter: Expr,
+}
+
+impl Parse for CbitUnwrap {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        Ok(Self {
+            kw_unwrap: input.parse()?,
+            paren: parenthesized!(content in input),
+            adapter: content.parse()?,
+        })
+    }
+}
+
+/// A `then <call>` running another iterator with the same body once the previous one completes.
+#[derive(Clone)]
+pub struct CbitThen {
+    pub kw_then: kw::then,
+    pub call: AnyCallExpr,
+}
+
+impl Parse for CbitThen {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            kw_then: input.parse()?,
             call: input.parse()?,
-            breaks: CbitForExprBreaks::parse(input)?,
-            body: input.parse()?,
         })
     }
 }
 
+/// Options configured through the attributes preceding a `cbit!` loop.
+#[derive(Default)]
+pub struct CbitOptions {
+    pub trace: bool,
+    pub hoist_args: bool,
+    pub dyn_callback: bool,
+    pub warn_unused_labels: bool,
+    /// The span of the `#[infinite]` attribute, if present, to which errors about the iterator
+    /// not actually being infinite are reported.
+    pub infinite: Option<Span>,
+
+    /// The labels of the enclosing `cbit!` loops (outermost first) if `break <depth>` and
+    /// `continue <depth>` are enabled. Nested loops are given their ancestors' labels by the loop
+    /// containing them.
+    pub numbered_breaks: Option<Vec<Lifetime>>,
+
+    /// A diverging function called instead of panicking if the body is suspended by an `.await`.
+    pub on_await_misuse: Option<Expr>,
+
+    /// A function mapping every value the loop is broken out of with before the loop yields it.
+    pub on_break: Option<Expr>,
+
+    /// The path to an enum used by the iterator in place of `ControlFlow`.
+    pub control_flow: Option<syn::Path>,
+}
+
+impl CbitOptions {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        for attr in attrs {
+            if attr.path().is_ident("trace") {
+                attr.meta.require_path_only()?;
+                options.trace = true;
+            } else if attr.path().is_ident("hoist_args") {
+                attr.meta.require_path_only()?;
+                options.hoist_args = true;
+            } else if attr.path().is_ident("numbered_breaks") {
+                // Nested loops may have both the user's attribute and the one listing their
+                // ancestors.
+                let levels = options.numbered_breaks.get_or_insert_with(Vec::new);
+                if !matches!(attr.meta, Meta::Path(_)) {
+                    levels.extend(
+                        attr.parse_args_with(Punctuated::<Lifetime, Token![,]>::parse_terminated)?,
+                    );
+                }
+            } else if attr.path().is_ident("dyn_callback") {
+                attr.meta.require_path_only()?;
+                options.dyn_callback = true;
+            } else if attr.path().is_ident("warn_unused_labels") {
+                attr.meta.require_path_only()?;
+                options.warn_unused_labels = true;
+            } else if attr.path().is_ident("infinite") {
+                attr.meta.require_path_only()?;
+                options.infinite = Some(attr.path().span());
+            } else if attr.path().is_ident("on_await_misuse") {
+                options.on_await_misuse = Some(attr.parse_args()?);
+            } else if attr.path().is_ident("on_break") {
+                options.on_break = Some(attr.parse_args()?);
+            } else if attr.path().is_ident("control_flow") {
+                options.control_flow = Some(attr.parse_args()?);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "unknown `cbit!` option; expected `#[trace]`, `#[hoist_args]`, `#[infinite]`, \
+                     `#[numbered_breaks]`, `#[dyn_callback]`, `#[warn_unused_labels]`, \
+                     `#[on_await_misuse(...)]`, `#[on_break(...)]`, or `#[control_flow(...)]`",
+                ));
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+#[derive(Clone)]
+pub enum CbitBinding {
+    /// A pattern introducing fresh bindings, just like in a regular `for` loop.
+    Pattern(Pat),
+
+    /// A destructuring assignment target (e.g. `(self.x, self.y)`) which is assigned to on every
+    /// iteration.
+    Assign(Expr),
+}
+
+impl Parse for CbitBinding {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // Patterns take precedence over assignment targets so that `(a, b)` introduces new bindings
+        // like it would in a regular `for` loop.
+        let fork = input.fork();
+        match Pat::parse_single(&fork) {
+            Ok(pat)
+                if fork.peek(Token![in])
+                    || fork.peek(Token![:])
+                    || (fork.peek(kw::first) && fork.peek2(Paren)) =>
+            {
+                input.advance_to(&fork);
+                Ok(Self::Pattern(pat))
+            }
+            pat_res => {
+                let target = Expr::parse_without_eager_brace(input)
+                    .map_err(|err| pat_res.err().unwrap_or(err))?;
+                reject_binding_defaults(&target)?;
+                Ok(Self::Assign(target))
+            }
+        }
+    }
+}
+
+/// Rejects attempts to give parts of a binding default values (e.g. `(a, b = 0)` or `(a, b?)`),
+/// which would otherwise parse as a destructuring assignment to variables which don't exist.
+fn reject_binding_defaults(target: &Expr) -> syn::Result<()> {
+    let message = match target {
+        Expr::Assign(_) => "bindings cannot specify default values",
+        Expr::Try(_) => "bindings cannot unwrap `Option`s with `?`",
+        Expr::Tuple(tuple) => return tuple.elems.iter().try_for_each(reject_binding_defaults),
+        Expr::Array(array) => return array.elems.iter().try_for_each(reject_binding_defaults),
+        Expr::Paren(paren) => return reject_binding_defaults(&paren.expr),
+        Expr::Call(call) => return call.args.iter().try_for_each(reject_binding_defaults),
+        Expr::Struct(strukt) => {
+            return strukt
+                .fields
+                .iter()
+                .try_for_each(|field| reject_binding_defaults(&field.expr))
+        }
+        _ => return Ok(()),
+    };
+
+    Err(syn::Error::new_spanned(
+        target,
+        format!(
+            "{message}; bind the `Option` itself and use e.g. `unwrap_or` on it in the body \
+             instead"
+        ),
+    ))
+}
+
 #[derive(Clone)]
 pub struct CbitForExprBreaks {
+    #[allow(dead_code)]
     pub kw_break: Token![break],
     pub lt: Punctuated<CbitForExprSingleBreak, Token![,]>,
 }
@@ -44,25 +651,132 @@ impl CbitForExprBreaks {
             return Ok(None);
         };
 
-        Ok(Some(Self {
-            kw_break,
-            lt: Punctuated::parse_separated_nonempty(input)?,
-        }))
+        let lt = Punctuated::<CbitForExprSingleBreak, Token![,]>::parse_separated_nonempty(input)?;
+
+        if let Some(kw_out) = lt
+            .iter()
+            .filter_map(|v| match &v.kind {
+                CbitLabelKind::Out(kw_out) => Some(kw_out),
+                _ => None,
+            })
+            .nth(1)
+        {
+            return Err(syn::Error::new(
+                kw_out.span,
+                "only one label can be declared with `out`",
+            ));
+        }
+
+        Ok(Some(Self { kw_break, lt }))
     }
 }
 
+pub mod kw {
+    syn::custom_keyword!(with);
+    syn::custom_keyword!(out);
+    syn::custom_keyword!(unwrap);
+    syn::custom_keyword!(first);
+    syn::custom_keyword!(step);
+    syn::custom_keyword!(raw);
+    syn::custom_keyword!(then);
+    syn::custom_keyword!(body);
+}
+
 #[derive(Clone)]
 pub struct CbitForExprSingleBreak {
-    pub kw_loop: Option<Token![loop]>,
+    pub kind: CbitLabelKind,
     pub lt: Lifetime,
+    pub with: Option<(kw::with, Expr)>,
+}
+
+impl CbitForExprSingleBreak {
+    /// Whether the body is allowed to `continue` to this label.
+    pub fn accepts_continue(&self) -> bool {
+        self.kind.accepts_continue()
+    }
+
+    /// Whether the body is allowed to `break` to this label with a value.
+    pub fn accepts_break_value(&self) -> bool {
+        matches!(
+            self.kind,
+            CbitLabelKind::Block | CbitLabelKind::Out(_) | CbitLabelKind::Loop(_)
+        )
+    }
 }
 
 impl Parse for CbitForExprSingleBreak {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        Ok(Self {
-            kw_loop: input.parse()?,
-            lt: input.parse()?,
-        })
+        let kind: CbitLabelKind = input.parse()?;
+        let lt = input.parse()?;
+
+        let with = if input.peek(kw::with) {
+            let kw_with = input.parse::<kw::with>()?;
+            if !kind.accepts_continue() {
+                return Err(syn::Error::new(
+                    kw_with.span,
+                    "only labels declared with `loop`, `while`, or `for` can be continued with a value",
+                ));
+            }
+
+            Some((kw_with, Expr::parse_without_eager_brace(input)?))
+        } else {
+            None
+        };
+
+        Ok(Self { kind, lt, with })
+    }
+}
+
+/// The kind of construct an external label is attached to.
+#[derive(Clone)]
+pub enum CbitLabelKind {
+    /// A labeled block, which can only be broken out of.
+    Block,
+
+    /// A labeled block which the body can also break out of with `break out`.
+    Out(kw::out),
+
+    /// A `loop`, which can be continued and broken out of with a value.
+    Loop(Token![loop]),
+
+    /// A `while` or `while let` loop, which can be continued but only broken out of without a value.
+    While(Token![while]),
+
+    /// A `for` loop, which can be continued but only broken out of without a value.
+    For(Token![for]),
+}
+
+impl CbitLabelKind {
+    /// Whether the construct can be continued.
+    pub fn accepts_continue(&self) -> bool {
+        !matches!(self, Self::Block | Self::Out(_))
+    }
+
+    /// The span of the keyword declaring the kind of construct, if any.
+    pub fn keyword_span(&self) -> Option<Span> {
+        match self {
+            Self::Block => None,
+            Self::Out(kw) => Some(kw.span),
+            Self::Loop(kw) => Some(kw.span),
+            Self::While(kw) => Some(kw.span),
+            Self::For(kw) => Some(kw.span),
+        }
+    }
+}
+
+impl Parse for CbitLabelKind {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![loop]) {
+            Ok(Self::Loop(input.parse()?))
+        } else if input.peek(Token![while]) {
+            Ok(Self::While(input.parse()?))
+        } else if input.peek(Token![for]) {
+            Ok(Self::For(input.parse()?))
+        } else if input.peek(kw::out) && input.peek2(Lifetime) {
+            Ok(Self::Out(input.parse()?))
+        } else {
+            Ok(Self::Block)
+        }
     }
 }
 
@@ -74,25 +788,363 @@ pub enum AnyCallExpr {
 
 impl Parse for AnyCallExpr {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        match input.parse::<Expr>()? {
+        // Without eager brace parsing, a bare path followed by the loop body isn't mistaken for a
+        // struct literal and can be reported as a missing call below.
+        let mut expr = Expr::parse_without_eager_brace(input)?;
+
+        // Calls forwarded through a `macro_rules!` metavariable such as `$f:expr` arrive wrapped in
+        // an invisible group, which we look through.
+        while let Expr::Group(group) = expr {
+            expr = *group.expr;
+        }
+
+        match expr {
             Expr::Call(func) => Ok(Self::Function(func)),
             Expr::MethodCall(method) => Ok(Self::Method(method)),
-            _ => Err(input.error("expected a function or method call")),
+            expr @ (Expr::Path(_) | Expr::Field(_)) => Err(syn::Error::new_spanned(
+                expr,
+                "expected a function or method call; add `(...)` with the iterator's arguments \
+                 to call it",
+            )),
+            expr @ Expr::Closure(_) => Err(syn::Error::new_spanned(
+                expr,
+                "expected a function or method call, not a closure; the body is passed to the \
+                 iterator as its closure so call the iterator function directly",
+            )),
+            expr @ Expr::Macro(_) => Err(syn::Error::new_spanned(
+                expr,
+                "expected a function or method call; macro invocations are not expanded before \
+                 the call is inspected so the call must be written out directly",
+            )),
+            expr => Err(syn::Error::new_spanned(
+                expr,
+                "expected a function or method call",
+            )),
+        }
+    }
+}
+
+impl AnyCallExpr {
+    pub fn attrs(&self) -> &[Attribute] {
+        match self {
+            Self::Function(call) => &call.attrs,
+            Self::Method(call) => &call.attrs,
+        }
+    }
+
+    pub fn args(&self) -> &Punctuated<Expr, Token![,]> {
+        match self {
+            Self::Function(call) => &call.args,
+            Self::Method(call) => &call.args,
         }
     }
+
+    pub fn args_mut(&mut self) -> &mut Punctuated<Expr, Token![,]> {
+        match self {
+            Self::Function(call) => &mut call.args,
+            Self::Method(call) => &mut call.args,
+        }
+    }
+}
+
+impl ToTokens for AnyCallExpr {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Self::Function(call) => call.to_tokens(tokens),
+            Self::Method(call) => call.to_tokens(tokens),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CallAndFn {
+    pub call: AnyCallExpr,
+    pub func: Expr,
+}
+
+impl Parse for CallAndFn {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let call = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let func = input.parse()?;
+
+        Ok(Self { call, func })
+    }
+}
+
+#[derive(Clone)]
+pub struct CallInitAndFn {
+    pub call: AnyCallExpr,
+    pub init: Expr,
+    pub func: Expr,
+}
+
+impl Parse for CallInitAndFn {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let call = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let init = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let func = input.parse()?;
+
+        Ok(Self { call, init, func })
+    }
 }
 
+/// The arguments to `for_range!`: a range, an optional step, and a closure-like body.
+#[derive(Clone)]
+pub struct ForRangeArgs {
+    pub range: Expr,
+    pub step: Option<(kw::step, Expr, Token![,])>,
+    pub closure: ExprClosure,
+}
+
+impl Parse for ForRangeArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let range = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let step = if input.peek(kw::step) {
+            Some((input.parse()?, input.parse()?, input.parse()?))
+        } else {
+            None
+        };
+        let closure = parse_loop_body_closure(input, "for_range!")?;
+
+        Ok(Self {
+            range,
+            step,
+            closure,
+        })
+    }
+}
+
+/// The arguments to `enumerate!`: an iterable and a closure-like body.
+#[derive(Clone)]
+pub struct EnumerateArgs {
+    pub iter: Expr,
+    pub closure: ExprClosure,
+}
+
+impl Parse for EnumerateArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let iter = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let closure = parse_loop_body_closure(input, "enumerate!")?;
+
+        Ok(Self { iter, closure })
+    }
+}
+
+/// The arguments to `for_iter!`: a regular `for` loop over an iterable.
+#[derive(Clone)]
+pub struct ForIterArgs {
+    pub label: Option<Label>,
+    #[allow(dead_code)]
+    pub kw_for: Token![for],
+    pub binding: Pat,
+    #[allow(dead_code)]
+    pub kw_in: Token![in],
+    pub iter: Expr,
+    pub body: Block,
+}
+
+impl Parse for ForIterArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            label: input.parse()?,
+            kw_for: input.parse()?,
+            binding: Pat::parse_multi_with_leading_vert(input)?,
+            kw_in: input.parse()?,
+            iter: Expr::parse_without_eager_brace(input)?,
+            body: input.parse()?,
+        })
+    }
+}
+
+/// The arguments to `zip!`: two iterables and a closure-like body.
+#[derive(Clone)]
+pub struct ZipArgs {
+    pub left: Expr,
+    pub right: Expr,
+    pub closure: ExprClosure,
+}
+
+impl Parse for ZipArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let left = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let right = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let closure = parse_loop_body_closure(input, "zip!")?;
+
+        Ok(Self {
+            left,
+            right,
+            closure,
+        })
+    }
+}
+
+/// The arguments to `windows!` and `chunks!`: a slice, a window or chunk size, and a closure-like
+/// body.
+#[derive(Clone)]
+pub struct WindowsArgs {
+    pub slice: Expr,
+    pub size: Expr,
+    pub closure: ExprClosure,
+}
+
+impl Parse for WindowsArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Self::parse_with(input, "windows!")
+    }
+}
+
+impl WindowsArgs {
+    /// Parses the arguments of `chunks!`, which share their shape with those of `windows!`.
+    pub fn parse_chunks(input: ParseStream) -> syn::Result<Self> {
+        Self::parse_with(input, "chunks!")
+    }
+
+    /// Parses the arguments of `macro_name`, which is named in errors about the closure-like body.
+    fn parse_with(input: ParseStream, macro_name: &str) -> syn::Result<Self> {
+        let slice = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let size = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let closure = parse_loop_body_closure(input, macro_name)?;
+
+        Ok(Self {
+            slice,
+            size,
+            closure,
+        })
+    }
+}
+
+/// Parses the closure-like loop body of a built-in loop such as `for_range!`, rejecting the closure
+/// features which make no sense on a loop body.
+fn parse_loop_body_closure(input: ParseStream, macro_name: &str) -> syn::Result<ExprClosure> {
+    let closure = input.parse::<ExprClosure>()?;
+
+    if closure.inputs.len() != 1 {
+        return Err(syn::Error::new(
+            closure.or1_token.span,
+            format!("the body of `{macro_name}` must take exactly one argument"),
+        ));
+    }
+
+    if let Some(tok) = closure
+        .lifetimes
+        .as_ref()
+        .map(|v| v.for_token.span)
+        .or(closure.constness.map(|v| v.span))
+        .or(closure.movability.map(|v| v.span))
+        .or(closure.asyncness.map(|v| v.span))
+        .or(closure.capture.map(|v| v.span))
+    {
+        return Err(syn::Error::new(
+            tok,
+            format!(
+                "the body of `{macro_name}` runs as a loop body and cannot take closure modifiers"
+            ),
+        ));
+    }
+
+    if let ReturnType::Type(arrow, _) = &closure.output {
+        return Err(syn::Error::new_spanned(
+            arrow,
+            format!("the body of `{macro_name}` runs as a loop body and cannot have a return type"),
+        ));
+    }
+
+    Ok(closure)
+}
+
+const MISSING_BODY_MESSAGE: &str =
+    "a `cbit!` loop requires a `{ ... }` or `=> <expr>` body after the \
+     iterator call (and optional `break` clause)";
+
 #[derive(Clone)]
 pub struct OpaqueBody {
-    pub brace: Brace,
+    pub delim: OpaqueBodyDelim,
     pub body: TokenStream,
 }
 
+/// How the body was introduced.
+#[derive(Clone)]
+pub enum OpaqueBodyDelim {
+    /// A regular `{ ... }` block.
+    Brace(Brace),
+
+    /// A `=> <expr>` shorthand for a body consisting of a single expression.
+    Arrow(Token![=>]),
+
+    /// An `@body <macro>!(...)` body produced entirely by a macro invocation.
+    Macro(
+        #[allow(dead_code)] Token![@],
+        #[allow(dead_code)] kw::body,
+        MacroDelimiter,
+    ),
+}
+
+impl OpaqueBodyDelim {
+    /// The span of the closing delimiter of the body or, for `=>` bodies which have none, of the
+    /// arrow.
+    pub fn close_span(&self) -> Span {
+        match self {
+            Self::Brace(brace) => brace.span.close(),
+            Self::Arrow(arrow) => arrow.spans[0],
+            Self::Macro(_, _, delim) => delim.span().close(),
+        }
+    }
+}
+
 impl Parse for OpaqueBody {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![=>]) {
+            let arrow = input.parse()?;
+
+            // The expression is only parsed to check that it is one. Like block bodies, the body
+            // itself is kept as opaque tokens.
+            let fork = input.fork();
+            fork.parse::<Expr>()?;
+            if !fork.is_empty() {
+                return Err(fork.error(
+                    "the `=>` form of a `cbit!` body must consist of a single expression; use a \
+                     `{ ... }` body instead",
+                ));
+            }
+
+            return Ok(Self {
+                delim: OpaqueBodyDelim::Arrow(arrow),
+                body: input.parse()?,
+            });
+        }
+
+        if input.peek(Token![@]) && input.peek2(kw::body) {
+            let at = input.parse()?;
+            let kw_body = input.parse()?;
+            let mac = input.parse::<Macro>().map_err(|err| {
+                syn::Error::new(
+                    err.span(),
+                    "expected a macro invocation producing the loop body after `@body`",
+                )
+            })?;
+
+            return Ok(Self {
+                delim: OpaqueBodyDelim::Macro(at, kw_body, mac.delimiter.clone()),
+                body: mac.to_token_stream(),
+            });
+        }
+
+        if !input.peek(Brace) {
+            return Err(input.error(MISSING_BODY_MESSAGE));
+        }
+
         let body;
         Ok(Self {
-            brace: braced!(body in input),
+            delim: OpaqueBodyDelim::Brace(braced!(body in input)),
             body: body.parse()?,
         })
     }